@@ -0,0 +1,15 @@
+use clap::Parser;
+use cli::MigrateCli;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let cli = MigrateCli::parse();
+
+    match cli::migrate(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
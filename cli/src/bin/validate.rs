@@ -0,0 +1,19 @@
+use clap::Parser;
+use cli::ValidatorCli;
+use formats::FormatRegistry;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let cli = ValidatorCli::parse();
+
+    let registry = FormatRegistry::with_options(&cli.format_options());
+
+    match cli::validate(cli, &registry) {
+        Ok(report) if report.is_valid() => ExitCode::SUCCESS,
+        Ok(_) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -1,5 +1,13 @@
-use clap::Parser;
-use formats::Format;
+use clap::{Args, Parser};
+use formats::bin_format::bin_parser::{BinOptions, BinVersion};
+use formats::csv_format::csv_parser::{CsvDialect, CsvOptions};
+use formats::text_format::text_parser::{TextMode, TextOptions, parse_field_order};
+use formats::{
+    DescriptionQuotes, ErrorStrategy, FormatOptions, ParseError, Profile, Record, TEXT_KEYS,
+    TimestampFormat, TimestampRange, Validator, ValueFormat, parse_utc_offset,
+};
+
+pub const AUTO_FORMAT: &str = "auto";
 
 #[derive(Parser)]
 #[command(name = "Comparer")]
@@ -8,12 +16,22 @@ use formats::Format;
 pub struct ComparerCli {
     #[arg(long)]
     pub file1: String,
-    #[arg(long, value_enum)]
-    pub format1: CliFormat,
+    /// Registered format name, or `auto` to detect it from the content
+    #[arg(long, default_value = AUTO_FORMAT)]
+    pub format1: String,
     #[arg(long)]
     pub file2: String,
-    #[arg(long, value_enum)]
-    pub format2: CliFormat,
+    /// Registered format name, or `auto` to detect it from the content
+    #[arg(long, default_value = AUTO_FORMAT)]
+    pub format2: String,
+    #[command(flatten)]
+    pub formats: FormatArgs,
+}
+
+impl ComparerCli {
+    pub fn format_options(&self) -> FormatOptions {
+        self.formats.options(ValueFormat::default())
+    }
 }
 
 #[derive(Parser)]
@@ -23,27 +41,289 @@ pub struct ComparerCli {
 pub struct ConverterCli {
     #[arg(long, value_name = "INPUT_FILE_NAME")]
     pub input: String,
-    #[arg(long, short, value_enum)]
-    pub input_format: CliFormat,
+    /// Registered format name, or `auto` to detect it from the content
+    #[arg(long, short, default_value = AUTO_FORMAT)]
+    pub input_format: String,
     #[arg(long, value_name = "OUTPUT_FILE_NAME")]
     pub output: String,
-    #[arg(long, short, value_enum)]
-    pub output_format: CliFormat,
+    /// Registered format name, guessed from the file extension when omitted
+    #[arg(long, short)]
+    pub output_format: Option<String>,
+    /// What to do with records that can not be parsed
+    #[arg(long, value_enum, default_value_t = CliErrorStrategy::Fail)]
+    pub on_error: CliErrorStrategy,
+    /// Append the raw input of rejected records to this file
+    #[arg(long, value_name = "QUARANTINE_FILE_NAME")]
+    pub quarantine: Option<String>,
+    /// Skip corrupted bytes of bin input up to the next valid record
+    #[arg(long)]
+    pub resync: bool,
+    /// How timestamps are written to csv and text, both are accepted on read
+    #[arg(long, value_enum, default_value_t = CliTimestampFormat::Millis)]
+    pub timestamp_format: CliTimestampFormat,
+    /// Time zone of written RFC 3339 timestamps, `Z` or `+HH:MM`/`-HH:MM`
+    #[arg(long, value_parser = utc_offset, default_value = "Z", allow_hyphen_values = true)]
+    pub utc_offset: i32,
+    /// Revision of written bin records, 2 adds a CRC-32 checksum to each one
+    #[arg(long, value_enum, default_value_t = CliBinVersion::V1)]
+    pub bin_version: CliBinVersion,
+    /// Start written bin files with a header naming their version and creation time
+    #[arg(long)]
+    pub bin_header: bool,
+    /// Comma separated order of the fields in written text records
+    #[arg(long, value_name = "KEYS", value_parser = text_field_order)]
+    pub text_field_order: Option<[&'static str; 8]>,
+    /// Comma separated extension columns of written csv, by default those of the first record
+    #[arg(long, value_name = "NAMES", value_delimiter = ',', value_parser = extension_name)]
+    pub csv_extensions: Option<Vec<String>>,
+    #[command(flatten)]
+    pub formats: FormatArgs,
+}
+
+impl ConverterCli {
+    pub fn format_options(&self) -> FormatOptions {
+        let timestamp = match self.timestamp_format {
+            CliTimestampFormat::Millis => TimestampFormat::Millis,
+            CliTimestampFormat::Rfc3339 => TimestampFormat::Rfc3339 {
+                offset_minutes: self.utc_offset,
+            },
+        };
+        let mut options = self.formats.options(ValueFormat {
+            timestamp,
+            ..ValueFormat::default()
+        });
+        options.bin.resync = self.resync;
+        options.bin.version = self.bin_version.into();
+        options.bin.header = self.bin_header;
+        options.text.field_order = self.text_field_order.unwrap_or(TEXT_KEYS);
+        options.csv.extensions = self.csv_extensions.clone();
+        options
+    }
+}
+
+#[derive(Parser)]
+#[command(name = "Migrate")]
+#[command(version = "1.0")]
+#[command(about = "Rewrite bin files with a file header and the latest record version", long_about = None)]
+pub struct MigrateCli {
+    #[arg(long, value_name = "INPUT_FILE_NAME")]
+    pub input: String,
+    #[arg(long, value_name = "OUTPUT_FILE_NAME")]
+    pub output: String,
+    /// Revision of the written records
+    #[arg(long, value_enum, default_value_t = CliBinVersion::V2)]
+    pub bin_version: CliBinVersion,
+}
+
+impl MigrateCli {
+    pub fn bin_options(&self) -> BinOptions {
+        BinOptions {
+            version: self.bin_version.into(),
+            header: true,
+            ..BinOptions::default()
+        }
+    }
+}
+
+#[derive(Parser)]
+#[command(name = "Validator")]
+#[command(version = "1.0")]
+#[command(about = "Check transactions against business rules", long_about = None)]
+pub struct ValidatorCli {
+    #[arg(long)]
+    pub file: String,
+    /// Registered format name, or `auto` to detect it from the content
+    #[arg(long, default_value = AUTO_FORMAT)]
+    pub format: String,
+    /// Name of a built-in rule to turn off, can be repeated
+    #[arg(long, value_name = "RULE")]
+    pub skip_rule: Vec<String>,
+    /// Earliest accepted timestamp in epoch milliseconds
+    #[arg(long)]
+    pub min_timestamp: Option<u64>,
+    /// Latest accepted timestamp in epoch milliseconds
+    #[arg(long)]
+    pub max_timestamp: Option<u64>,
+    #[command(flatten)]
+    pub formats: FormatArgs,
+}
+
+impl ValidatorCli {
+    pub fn format_options(&self) -> FormatOptions {
+        self.formats.options(ValueFormat::default())
+    }
+
+    pub fn validator(&self) -> Result<Validator, ParseError> {
+        let defaults = TimestampRange::default();
+        let mut validator =
+            Validator::default()
+                .without("timestamp-range")
+                .with_rule(TimestampRange {
+                    min: self.min_timestamp.unwrap_or(defaults.min),
+                    max: self.max_timestamp.unwrap_or(defaults.max),
+                });
+
+        for name in &self.skip_rule {
+            if !validator.rule_names().contains(&name.as_str()) {
+                return Err(ParseError::InvalidArgument("unknown validation rule"));
+            }
+            validator = validator.without(name);
+        }
+
+        Ok(validator)
+    }
+}
+
+/// Options of the built-in formats shared by all commands
+#[derive(Args)]
+pub struct FormatArgs {
+    /// Number of decimals of amounts in csv and text, 0 for integer minor units
+    #[arg(long, default_value_t = 0)]
+    pub amount_scale: u8,
+    /// Csv dialect: default, rfc4180, excel, excel-semicolon or tsv
+    #[arg(long, value_parser = csv_dialect, default_value = "default")]
+    pub csv_dialect: CsvDialect,
+    /// Csv delimiter replacing the one of the dialect, an ASCII character or `\t`
+    #[arg(long, value_parser = csv_delimiter)]
+    pub csv_delimiter: Option<u8>,
+    /// Csv files have no header line, columns are in the standard order
+    #[arg(long)]
+    pub csv_no_header: bool,
+    /// Quotes around descriptions in csv and text are part of them, as in older files
+    #[arg(long)]
+    pub legacy_descriptions: bool,
+    /// TOML mapping profile of csv input with foreign columns and values
+    #[arg(long, value_name = "PROFILE_FILE_NAME", value_parser = profile)]
+    pub profile: Option<Profile>,
+    /// How keys of text records are read: strict rejects repeated keys, permissive
+    /// accepts keys in any case and ignores invalid ones
+    #[arg(long, value_enum, default_value_t = CliTextMode::Strict)]
+    pub text_mode: CliTextMode,
+}
+
+impl FormatArgs {
+    /// Options for these arguments, `values` gives the spelling of values not set by them
+    pub fn options(&self, values: ValueFormat) -> FormatOptions {
+        let values = ValueFormat {
+            amount_scale: self.amount_scale,
+            description: if self.legacy_descriptions {
+                DescriptionQuotes::Legacy
+            } else {
+                DescriptionQuotes::Syntax
+            },
+            ..values
+        };
+        let mut dialect = self.csv_dialect.clone();
+        if let Some(delimiter) = self.csv_delimiter {
+            dialect.delimiter = delimiter;
+        }
+        dialect.header &= !self.csv_no_header;
+
+        FormatOptions {
+            bin: BinOptions::default(),
+            csv: CsvOptions {
+                dialect,
+                values: values.clone(),
+                profile: self.profile.clone().unwrap_or_default(),
+                ..CsvOptions::default()
+            },
+            text: TextOptions {
+                values,
+                mode: self.text_mode.into(),
+                ..TextOptions::default()
+            },
+        }
+    }
+}
+
+fn csv_delimiter(value: &str) -> Result<u8, String> {
+    match value.as_bytes() {
+        [b'\\', b't'] => Ok(b'\t'),
+        [byte] if byte.is_ascii() && !matches!(byte, b'"' | b'\r' | b'\n') => Ok(*byte),
+        _ => Err(format!("`{}` can not be a csv delimiter", value)),
+    }
+}
+
+fn extension_name(value: &str) -> Result<String, String> {
+    match Record::is_extension_name(value) {
+        true => Ok(value.to_string()),
+        false => Err(format!("`{}` can not name an extension field", value)),
+    }
+}
+
+fn text_field_order(value: &str) -> Result<[&'static str; 8], String> {
+    parse_field_order(value).map_err(|e| e.to_string())
+}
+
+fn profile(value: &str) -> Result<Profile, String> {
+    Profile::load(value).map_err(|e| e.to_string())
+}
+
+fn csv_dialect(value: &str) -> Result<CsvDialect, String> {
+    CsvDialect::preset(value).ok_or_else(|| {
+        format!(
+            "unknown csv dialect `{}`, expected one of {}",
+            value,
+            CsvDialect::PRESETS.join(", ")
+        )
+    })
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CliTimestampFormat {
+    Millis,
+    Rfc3339,
+}
+
+fn utc_offset(value: &str) -> Result<i32, String> {
+    parse_utc_offset(value).ok_or_else(|| format!("invalid UTC offset `{}`", value))
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CliBinVersion {
+    #[value(name = "1")]
+    V1,
+    #[value(name = "2")]
+    V2,
+}
+
+impl From<CliBinVersion> for BinVersion {
+    fn from(value: CliBinVersion) -> Self {
+        match value {
+            CliBinVersion::V1 => BinVersion::V1,
+            CliBinVersion::V2 => BinVersion::V2,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CliTextMode {
+    Strict,
+    Permissive,
+}
+
+impl From<CliTextMode> for TextMode {
+    fn from(value: CliTextMode) -> Self {
+        match value {
+            CliTextMode::Strict => TextMode::Strict,
+            CliTextMode::Permissive => TextMode::Permissive,
+        }
+    }
 }
 
-#[derive(clap::ValueEnum, Clone, Debug)]
-pub enum CliFormat {
-    Text,
-    Csv,
-    Bin,
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CliErrorStrategy {
+    Fail,
+    Skip,
+    Collect,
 }
 
-impl From<CliFormat> for formats::Format {
-    fn from(value: CliFormat) -> Self {
+impl From<CliErrorStrategy> for ErrorStrategy {
+    fn from(value: CliErrorStrategy) -> Self {
         match value {
-            CliFormat::Bin => Format::Bin,
-            CliFormat::Csv => Format::Csv,
-            CliFormat::Text => Format::Text,
+            CliErrorStrategy::Fail => ErrorStrategy::FailFast,
+            CliErrorStrategy::Skip => ErrorStrategy::Skip,
+            CliErrorStrategy::Collect => ErrorStrategy::Collect,
         }
     }
 }
@@ -0,0 +1,489 @@
+use crate::{AUTO_FORMAT, CliError, ComparerCli, ConverterCli, MigrateCli, ValidatorCli};
+use formats::bin_format::bin_parser::{self, BinWriter};
+use formats::{
+    Diagnostic, ErrorStrategy, Format, FormatRegistry, ParseError, ParseOptions, Record,
+    RecordWriter, Records, ValidationReport,
+};
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+
+const DETECT_SAMPLE_SIZE: u64 = 4096;
+
+/// Convert `cli.input` to `cli.output` with the formats known to `registry`
+pub fn convert(cli: ConverterCli, registry: &FormatRegistry) -> Result<(), CliError> {
+    let input_error = |e| CliError::new(&cli.input, e);
+    let output_error = |e| CliError::new(&cli.output, e);
+
+    let to_format = resolve_format(registry, &cli.output, cli.output_format.as_deref())
+        .map_err(output_error)?;
+    check_output(&cli.input, &cli.output).map_err(output_error)?;
+    if let Some(name) = &cli.quarantine {
+        let quarantine_error = |e| CliError::new(name, e);
+        check_output(&cli.input, name).map_err(quarantine_error)?;
+        if same_file(&cli.output, name) {
+            return Err(quarantine_error(ParseError::InvalidArgument(
+                "quarantine file must not be the output file",
+            )));
+        }
+    }
+
+    let strategy = ErrorStrategy::from(cli.on_error);
+    let print_rejected = strategy == ErrorStrategy::Collect;
+    // rejected input can only be quarantined when it is collected
+    let options = match (strategy, &cli.quarantine) {
+        (ErrorStrategy::Skip, Some(_)) => ParseOptions::new(ErrorStrategy::Collect),
+        _ => ParseOptions::new(strategy),
+    };
+
+    let mut transactions =
+        options.apply(open_records(registry, &cli.input, &cli.input_format).map_err(input_error)?);
+
+    let mut quarantine = match &cli.quarantine {
+        Some(name) => {
+            let file = OpenOptions::new().append(true).create(true).open(name);
+            Some(file.map_err(|e| CliError::new(name, e.into()))?)
+        }
+        None => None,
+    };
+
+    let to = File::create(&cli.output).map_err(|e| output_error(ParseError::Io(e)))?;
+    let mut writer = registry.writer(&to_format, to).map_err(output_error)?;
+
+    while let Some(transaction) = transactions.next() {
+        let transaction = transaction.map_err(input_error)?;
+        writer.write_record(&transaction).map_err(output_error)?;
+
+        let rejected = transactions.take_diagnostics();
+        report_rejected(&cli, rejected, print_rejected, &mut quarantine)?;
+    }
+    let rejected = transactions.take_diagnostics();
+    report_rejected(&cli, rejected, print_rejected, &mut quarantine)?;
+
+    writer.finish().map_err(output_error)?;
+
+    match transactions.rejected() {
+        0 => println!("Converted successfully"),
+        n => println!("Converted successfully, {} broken records rejected", n),
+    }
+    Ok(())
+}
+
+fn report_rejected(
+    cli: &ConverterCli,
+    rejected: Vec<Diagnostic>,
+    print: bool,
+    quarantine: &mut Option<File>,
+) -> Result<(), CliError> {
+    for diagnostic in rejected {
+        if let (Some(file), Some(name)) = (quarantine.as_mut(), &cli.quarantine) {
+            file.write_all(&diagnostic.raw)
+                .map_err(|e| CliError::new(name, e.into()))?;
+        }
+        if print {
+            eprintln!("{}", CliError::new(&cli.input, diagnostic.error));
+        }
+    }
+    Ok(())
+}
+
+/// Compare the transactions of `cli.file1` and `cli.file2` with the formats known to `registry`
+pub fn compare(cli: ComparerCli, registry: &FormatRegistry) -> Result<(), CliError> {
+    let file_name_1 = cli.file1;
+    let file_name_2 = cli.file2;
+
+    let transactions_1 = read_transactions(registry, &file_name_1, &cli.format1)
+        .map_err(|e| CliError::new(&file_name_1, e))?;
+    let transactions_2 = read_transactions(registry, &file_name_2, &cli.format2)
+        .map_err(|e| CliError::new(&file_name_2, e))?;
+
+    if is_equal_transactions(&transactions_1, &transactions_2) {
+        println!(
+            "The transaction records in '{}' and '{}' are identical.",
+            file_name_1, file_name_2
+        )
+    } else {
+        println!(
+            "The transaction records in '{}' and '{}' are NOT identical.",
+            file_name_1, file_name_2
+        )
+    }
+
+    Ok(())
+}
+
+/// Check the transactions of `cli.file` against the configured rules and print every violation
+pub fn validate(
+    cli: ValidatorCli,
+    registry: &FormatRegistry,
+) -> Result<ValidationReport, CliError> {
+    let file_error = |e| CliError::new(&cli.file, e);
+
+    let mut validator = cli.validator().map_err(file_error)?;
+    let mut report = ValidationReport::default();
+
+    for transaction in open_records(registry, &cli.file, &cli.format).map_err(file_error)? {
+        let transaction = transaction.map_err(file_error)?;
+
+        for violation in validator.check(&transaction) {
+            println!("{}: {}", cli.file, violation);
+            report.violations.push(violation);
+        }
+        report.records += 1;
+    }
+
+    match report.violations.len() {
+        0 => println!("All {} transactions are valid", report.records),
+        n => println!("{} rule violations in {} transactions", n, report.records),
+    }
+    Ok(report)
+}
+
+/// Rewrite the bin file `cli.input`, with or without a header, as `cli.output` with a header
+pub fn migrate(cli: MigrateCli) -> Result<(), CliError> {
+    let input_error = |e| CliError::new(&cli.input, e);
+    let output_error = |e| CliError::new(&cli.output, e);
+
+    let from = File::open(&cli.input).map_err(|e| input_error(ParseError::Io(e)))?;
+    check_output(&cli.input, &cli.output).map_err(output_error)?;
+    let to = File::create(&cli.output).map_err(|e| output_error(ParseError::Io(e)))?;
+    let mut writer = BinWriter::with_options(to, &cli.bin_options());
+
+    let mut migrated = 0;
+    for record in bin_parser::records(from) {
+        writer
+            .write_record(&record.map_err(input_error)?)
+            .map_err(output_error)?;
+        migrated += 1;
+    }
+    writer.finish().map_err(output_error)?;
+
+    println!("Migrated {} records", migrated);
+    Ok(())
+}
+
+/// Creating `output` empties it, which must not happen to an input that is still to be read
+fn check_output(input: &str, output: &str) -> Result<(), ParseError> {
+    if same_file(input, output) {
+        return Err(ParseError::InvalidArgument(
+            "output must not be the input file",
+        ));
+    }
+    Ok(())
+}
+
+/// Whether the paths name the same file, which does not need to exist yet
+fn same_file(a: &str, b: &str) -> bool {
+    matches!((resolve(a), resolve(b)), (Some(a), Some(b)) if a == b)
+}
+
+/// Absolute path of `file` with links resolved, for a missing file those of its directory
+fn resolve(file: &str) -> Option<PathBuf> {
+    let path = Path::new(file);
+    if let Ok(path) = std::fs::canonicalize(path) {
+        return Some(path);
+    }
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    Some(std::fs::canonicalize(parent).ok()?.join(path.file_name()?))
+}
+
+/// Lazily read transactions of `file`, detecting its format from the content when `format` is `auto`
+pub fn open_records(
+    registry: &FormatRegistry,
+    file: &str,
+    format: &str,
+) -> Result<Records, ParseError> {
+    let mut f = File::open(file).map_err(ParseError::Io)?;
+
+    if format != AUTO_FORMAT {
+        return registry.records(format, f);
+    }
+
+    let mut sample = Vec::new();
+    Read::by_ref(&mut f)
+        .take(DETECT_SAMPLE_SIZE)
+        .read_to_end(&mut sample)?;
+    let detected = Format::detect(&sample)?;
+
+    registry.records(detected.name(), Cursor::new(sample).chain(f))
+}
+
+/// Name of the format to use for `file`, either given explicitly or guessed from its extension
+pub fn resolve_format(
+    registry: &FormatRegistry,
+    file: &str,
+    format: Option<&str>,
+) -> Result<String, ParseError> {
+    let name = match format {
+        Some(name) => name,
+        None => registry
+            .name_for_path(file)
+            .ok_or(ParseError::InvalidArgument(
+                "format can not be guessed from the file extension",
+            ))?,
+    };
+
+    if !registry.contains(name) {
+        return Err(ParseError::UnknownFormat(name.to_string()));
+    }
+
+    Ok(name.to_string())
+}
+
+fn read_transactions(
+    registry: &FormatRegistry,
+    file: &str,
+    format: &str,
+) -> Result<Vec<Record>, ParseError> {
+    open_records(registry, file, format)?.collect()
+}
+
+fn is_equal_transactions(transactions_1: &[Record], transactions_2: &[Record]) -> bool {
+    let mut transactions = HashSet::new();
+    transactions.extend(transactions_1);
+
+    for t in transactions_2 {
+        if !transactions.remove(&t) {
+            // if not contains then transactions not identical
+            return false;
+        }
+    }
+
+    transactions.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use formats::{TransactionStatus, TransactionType};
+
+    fn records_mock_1() -> [Record; 2] {
+        [
+            Record {
+                tx_id: 1000000000000000,
+                tx_type: TransactionType::Deposit,
+                from_user_id: 0,
+                to_user_id: 9223372036854775807,
+                amount: 100.into(),
+                timestamp: 1633036860000.into(),
+                status: TransactionStatus::Failure,
+                description: "\"Record number 1\"".to_string(),
+                extensions: Default::default(),
+            },
+            Record {
+                tx_id: 1000000000000001,
+                tx_type: TransactionType::Transfer,
+                from_user_id: 9223372036854775807,
+                to_user_id: 9223372036854775807,
+                amount: 200.into(),
+                timestamp: 1633036920000.into(),
+                status: TransactionStatus::Pending,
+                description: "\"Record number 2\"".to_string(),
+                extensions: Default::default(),
+            },
+        ]
+    }
+
+    // Different
+    fn records_mock_2() -> [Record; 2] {
+        [
+            Record {
+                tx_id: 1000000000000000,
+                tx_type: TransactionType::Deposit,
+                from_user_id: 0,
+                to_user_id: 9223372036854775807,
+                amount: 100.into(),
+                timestamp: 1633036860000.into(),
+                status: TransactionStatus::Failure,
+                description: "\"Record number 1\"".to_string(),
+                extensions: Default::default(),
+            },
+            Record {
+                tx_id: 1000000000000002,
+                tx_type: TransactionType::Withdrawal,
+                from_user_id: 599094029349995112,
+                to_user_id: 0,
+                amount: 300.into(),
+                timestamp: 1633036980000.into(),
+                status: TransactionStatus::Success,
+                description: "\"Record number 3\"".to_string(),
+                extensions: Default::default(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_equal_transactions() {
+        let is_equal = is_equal_transactions(&records_mock_1(), &records_mock_1());
+
+        assert!(is_equal);
+    }
+
+    #[test]
+    fn test_not_equal_transactions() {
+        let is_equal = is_equal_transactions(&records_mock_1(), &records_mock_2());
+
+        assert!(!is_equal);
+    }
+
+    #[test]
+    fn test_resolve_format() {
+        let registry = FormatRegistry::default();
+
+        assert_eq!(resolve_format(&registry, "a.csv", None).unwrap(), "csv");
+        assert_eq!(
+            resolve_format(&registry, "a.csv", Some("bin")).unwrap(),
+            "bin"
+        );
+        assert!(matches!(
+            resolve_format(&registry, "a.csv", Some("cs")),
+            Err(ParseError::UnknownFormat(_))
+        ));
+        assert!(resolve_format(&registry, "a", None).is_err());
+    }
+
+    #[test]
+    fn test_convert_onto_input() {
+        use clap::Parser;
+
+        let file = std::env::temp_dir().join(format!("convert_{}.csv", std::process::id()));
+        let file = file.to_str().unwrap().to_string();
+        std::fs::copy("../records_example.csv", &file).unwrap();
+        let cli = ConverterCli::parse_from(["converter", "--input", &file, "--output", &file]);
+
+        let error = convert(cli, &FormatRegistry::default()).unwrap_err();
+        assert!(matches!(error.error, ParseError::InvalidArgument(_)));
+        assert_eq!(
+            std::fs::read(&file).unwrap(),
+            std::fs::read("../records_example.csv").unwrap()
+        );
+        std::fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    fn test_quarantine_appends() {
+        use clap::Parser;
+
+        let dir = std::env::temp_dir();
+        let name = |ext: &str| {
+            let file = dir.join(format!("quarantine_{}.{ext}", std::process::id()));
+            file.to_str().unwrap().to_string()
+        };
+        let (input, output, quarantine) = (name("csv"), name("bin"), name("txt"));
+        let header = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n";
+        let bad = "1,DEPOSIT,0,1,100,1633036860000,LOST,\"bad\"\n";
+        let args = [
+            "converter",
+            "--input",
+            &input,
+            "--output",
+            &output,
+            "--output-format",
+            "bin",
+            "--on-error",
+            "skip",
+            "--quarantine",
+            &quarantine,
+        ];
+
+        let error = convert(ConverterCli::parse_from(args), &FormatRegistry::default());
+        assert!(error.is_err());
+        assert!(!Path::new(&quarantine).exists());
+
+        std::fs::write(&input, format!("{header}{bad}")).unwrap();
+        std::fs::write(&quarantine, "earlier\n").unwrap();
+        convert(ConverterCli::parse_from(args), &FormatRegistry::default()).unwrap();
+        let kept = std::fs::read_to_string(&quarantine).unwrap();
+        assert!(kept.starts_with("earlier\n"));
+        assert!(kept.contains("LOST"));
+
+        let onto_output = args.map(|arg| if arg == quarantine { &output } else { arg });
+        let error = convert(
+            ConverterCli::parse_from(onto_output),
+            &FormatRegistry::default(),
+        );
+        assert!(matches!(
+            error.unwrap_err().error,
+            ParseError::InvalidArgument(_)
+        ));
+        std::fs::remove_file(&output).unwrap();
+        let error = convert(
+            ConverterCli::parse_from(onto_output),
+            &FormatRegistry::default(),
+        );
+        assert!(matches!(
+            error.unwrap_err().error,
+            ParseError::InvalidArgument(_)
+        ));
+        assert!(!Path::new(&output).exists());
+
+        for file in [input, quarantine] {
+            std::fs::remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_convert_csv_extensions() {
+        use clap::Parser;
+
+        let name = |ext: &str| {
+            let file =
+                std::env::temp_dir().join(format!("extensions_{}.{ext}", std::process::id()));
+            file.to_str().unwrap().to_string()
+        };
+        let (input, output) = (name("bin"), name("csv"));
+        let mut records = records_mock_1();
+        records[1]
+            .extensions
+            .insert("MERCHANT_REF".to_string(), "M-42".to_string());
+        bin_parser::write_to(&mut File::create(&input).unwrap(), records.clone()).unwrap();
+
+        let args = ["converter", "--input", &input, "--output", &output];
+        let error = convert(ConverterCli::parse_from(args), &FormatRegistry::default());
+        assert!(matches!(
+            error.unwrap_err().error,
+            ParseError::InvalidExtension(_)
+        ));
+
+        let declared = [&args[..], &["--csv-extensions", "MERCHANT_REF"]].concat();
+        let cli = ConverterCli::parse_from(declared);
+        let registry = FormatRegistry::with_options(&cli.format_options());
+        convert(cli, &registry).unwrap();
+        assert_eq!(
+            read_transactions(&FormatRegistry::default(), &output, "csv").unwrap(),
+            records
+        );
+
+        for file in [input, output] {
+            std::fs::remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_migrate() {
+        let output = std::env::temp_dir().join(format!("migrate_{}.bin", std::process::id()));
+        let output = output.to_str().unwrap().to_string();
+        let cli = |input: &str, output: &str| MigrateCli {
+            input: input.to_string(),
+            output: output.to_string(),
+            bin_version: crate::CliBinVersion::V2,
+        };
+
+        migrate(cli("../records_example.bin", &output)).unwrap();
+        let migrated = std::fs::read(&output).unwrap();
+        assert!(migrated.starts_with(b"YPBF"));
+        assert_eq!(
+            bin_parser::read_from(&migrated[..]).unwrap(),
+            read_transactions(&FormatRegistry::default(), "../records_example.bin", "bin").unwrap()
+        );
+
+        let error = migrate(cli(&output, &output)).unwrap_err();
+        assert!(matches!(error.error, ParseError::InvalidArgument(_)));
+        assert_eq!(std::fs::read(&output).unwrap(), migrated);
+        std::fs::remove_file(&output).unwrap();
+    }
+}
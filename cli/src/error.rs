@@ -0,0 +1,30 @@
+use formats::ParseError;
+use std::error::Error;
+use std::fmt::Display;
+
+/// Parse error together with the file it happened in
+#[derive(Debug)]
+pub struct CliError {
+    pub file: String,
+    pub error: ParseError,
+}
+
+impl CliError {
+    pub fn new(file: &str, error: ParseError) -> Self {
+        CliError {
+            file: file.to_string(),
+            error,
+        }
+    }
+}
+
+impl Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.error.position() {
+            Some(_) => write!(f, "{}:{}", self.file, self.error),
+            None => write!(f, "{}: {}", self.file, self.error),
+        }
+    }
+}
+
+impl Error for CliError {}
@@ -1,3 +1,10 @@
 mod cli_types;
+mod commands;
+mod error;
 
-pub use cli_types::{CliFormat, ComparerCli, ConverterCli};
+pub use cli_types::{
+    AUTO_FORMAT, CliBinVersion, CliErrorStrategy, CliTimestampFormat, ComparerCli, ConverterCli,
+    FormatArgs, MigrateCli, ValidatorCli,
+};
+pub use commands::{compare, convert, migrate, open_records, resolve_format, validate};
+pub use error::CliError;
@@ -2,21 +2,96 @@ use std::error::Error;
 use std::fmt::Display;
 use std::io;
 
+use crate::model::Format;
+
 #[derive(Debug)]
 pub enum ParseError {
     TransactionType(ParseTransactionTypeError),
     TransactionStatus(ParseTransactionStatusError),
     RecordDamaged(u64),
-    UnexpectedEof { needed: usize, got: usize },
+    ChecksumMismatch(u64),
+    UnexpectedEof {
+        needed: usize,
+        got: usize,
+    },
     UnexpectedRecordSize(u32),
     InvalidMagic,
+    UnsupportedVersion(u16),
+    HeaderMismatch,
     RecordTooShort,
     Io(io::Error),
     InvalidUtf8(std::string::FromUtf8Error),
-    InvalidNumber,
+    InvalidNumber(&'static str),
     MalformedLine,
     MissingField(&'static str),
+    MissingColumn(&'static str),
+    InvalidProfile(String),
+    InvalidExtension(String),
+    DuplicateKey(String),
     InvalidArgument(&'static str),
+    UnknownFormat(String),
+    FormatNotDetected,
+    AmbiguousFormat(Vec<Format>),
+    CorruptedBytes {
+        start: u64,
+        end: u64,
+    },
+    Located {
+        position: Position,
+        error: Box<ParseError>,
+    },
+}
+
+impl ParseError {
+    /// Attach the position of the input where the error happened, keeps an already attached one
+    pub fn at(self, position: Position) -> ParseError {
+        match self {
+            located @ ParseError::Located { .. } => located,
+            error => ParseError::Located {
+                position,
+                error: Box::new(error),
+            },
+        }
+    }
+
+    pub fn position(&self) -> Option<Position> {
+        match self {
+            ParseError::Located { position, .. } => Some(*position),
+            _ => None,
+        }
+    }
+
+    /// The error itself without its position
+    pub fn kind(&self) -> &ParseError {
+        match self {
+            ParseError::Located { error, .. } => error,
+            error => error,
+        }
+    }
+}
+
+/// Place in the input an error refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Position {
+    /// 1-based line and column of csv and text inputs
+    Line { line: usize, column: Option<usize> },
+    /// Offset of the record start and 0-based record index of bin inputs
+    Byte { offset: u64, record: usize },
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Position::Line { line, column: None } => write!(f, "{}", line),
+            Position::Line {
+                line,
+                column: Some(column),
+            } => write!(f, "{}:{}", line, column),
+            Position::Byte { offset, record } => {
+                write!(f, "byte {} (record {})", offset, record)
+            }
+        }
+    }
 }
 
 impl Display for ParseError {
@@ -25,6 +100,9 @@ impl Display for ParseError {
             Self::TransactionType(e) => write!(f, "Transaction type error: {}", e),
             Self::TransactionStatus(e) => write!(f, "Transaction status error: {}", e),
             Self::RecordDamaged(tx_id) => write!(f, "Record with id '{}' damaged", tx_id),
+            Self::ChecksumMismatch(tx_id) => {
+                write!(f, "Checksum mismatch in record with id '{}'", tx_id)
+            }
             Self::UnexpectedEof { needed, got } => {
                 write!(f, "Unexpected Eof, needed {}, got {}", needed, got)
             }
@@ -32,13 +110,31 @@ impl Display for ParseError {
                 write!(f, "Unexpected Record size {}", s)
             }
             Self::InvalidMagic => write!(f, "Invalid magic"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "Unsupported format version {}", version)
+            }
+            Self::HeaderMismatch => write!(f, "Record version does not match the file header"),
             Self::RecordTooShort => write!(f, "Record too short"),
             Self::Io(e) => write!(f, "Io error: {}", e),
             Self::InvalidUtf8(e) => write!(f, "Invalid Utf-8 format {}", e),
-            Self::InvalidNumber => write!(f, "Invalid number"),
+            Self::InvalidNumber(field) => write!(f, "Invalid number in {}", field),
             Self::MalformedLine => write!(f, "Malformed line"),
             Self::MissingField(field) => write!(f, "Missing field {}", field),
+            Self::MissingColumn(column) => write!(f, "Missing column {}", column),
+            Self::InvalidProfile(message) => write!(f, "Invalid profile: {}", message),
+            Self::InvalidExtension(name) => write!(f, "Invalid extension field '{}'", name),
+            Self::DuplicateKey(key) => write!(f, "Duplicate key '{}'", key),
             Self::InvalidArgument(message) => write!(f, "Invalid argument: {}", message),
+            Self::UnknownFormat(name) => write!(f, "Unknown format '{}'", name),
+            Self::FormatNotDetected => write!(f, "Format can not be detected"),
+            Self::AmbiguousFormat(formats) => {
+                let names: Vec<&str> = formats.iter().map(Format::name).collect();
+                write!(f, "Ambiguous format, could be any of {}", names.join(", "))
+            }
+            Self::CorruptedBytes { start, end } => {
+                write!(f, "Corrupted bytes {}..{} skipped", start, end)
+            }
+            Self::Located { position, error } => write!(f, "{}: {}", position, error),
         }
     }
 }
@@ -1,5 +1,19 @@
 mod error;
 mod model;
+mod options;
+mod validation;
+mod value;
 
-pub use error::{ParseError, ParseTransactionStatusError, ParseTransactionTypeError};
-pub use model::{Format, Reader, Record, TextRecordDraft, TransactionStatus, TransactionType};
+pub use error::{ParseError, ParseTransactionStatusError, ParseTransactionTypeError, Position};
+pub use model::{
+    BIN_FILE_MAGIC, BIN_MAGIC, BIN_MAGIC_V2, CSV_HEADER, Format, Reader, Record, RecordSource,
+    RecordWriter, TEXT_KEYS, TextRecordDraft, TransactionStatus, TransactionType, Writer,
+};
+pub use options::{Diagnostic, ErrorStrategy, Lenient, ParseOptions, ParseReport};
+pub use validation::{
+    DepositFromZero, MAX_TIMESTAMP, MIN_TIMESTAMP, PositiveAmount, Rule, TimestampRange,
+    UniqueTxId, ValidationReport, Validator, Violation, WithdrawalToZero,
+};
+pub use value::{
+    Amount, DescriptionQuotes, Timestamp, TimestampFormat, ValueFormat, parse_utc_offset,
+};
@@ -1,6 +1,11 @@
-use std::{fmt::Display, fs::File};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::io::{Read, Write};
 
 use crate::error::{ParseError, ParseTransactionStatusError, ParseTransactionTypeError};
+use crate::options::{ParseOptions, ParseReport};
+use crate::value::{Amount, Timestamp};
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct Record {
@@ -8,13 +13,65 @@ pub struct Record {
     pub tx_type: TransactionType,
     pub from_user_id: u64,
     pub to_user_id: u64,
-    pub amount: u64,
-    pub timestamp: u64,
+    pub amount: Amount,
+    pub timestamp: Timestamp,
     pub status: TransactionStatus,
     pub description: String,
+    /// Fields beyond the standard ones by name, e.g. a merchant reference
+    pub extensions: BTreeMap<String, String>,
 }
 
 impl Record {
+    /// Whether `name` can name an extension field in every format: it is made of ASCII letters,
+    /// digits, `_`, `-` and `.` only and is not a standard field name. Anything else, e.g. a
+    /// leading `#` which starts a comment in text, or a csv quote or delimiter, would not survive
+    /// a round trip
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// assert!(core::Record::is_extension_name("MERCHANT_REF"));
+    /// assert!(!core::Record::is_extension_name("amount"));
+    /// assert!(!core::Record::is_extension_name("A:B"));
+    /// assert!(!core::Record::is_extension_name("#REF"));
+    /// ```
+    pub fn is_extension_name(name: &str) -> bool {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+            && !TEXT_KEYS.iter().any(|key| key.eq_ignore_ascii_case(name))
+    }
+
+    /// Name of the first extension field that can not be kept in every format, either for its
+    /// name or for an empty value, which csv can not tell apart from a missing field
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut record = core::Record {
+    ///     tx_id: 1000000000000000,
+    ///     tx_type: core::TransactionType::Deposit,
+    ///     from_user_id: 0,
+    ///     to_user_id: 9223372036854775807,
+    ///     amount: core::Amount::from(100),
+    ///     timestamp: core::Timestamp::from(1633036860000),
+    ///     status: core::TransactionStatus::Failure,
+    ///     description: String::from("Record number 1"),
+    ///     extensions: Default::default(),
+    /// };
+    /// record.extensions.insert("CHANNEL".to_string(), "web".to_string());
+    /// assert_eq!(record.invalid_extension(), None);
+    /// record.extensions.insert("MERCHANT_REF".to_string(), String::new());
+    /// assert_eq!(record.invalid_extension(), Some("MERCHANT_REF"));
+    /// ```
+    pub fn invalid_extension(&self) -> Option<&str> {
+        self.extensions
+            .iter()
+            .find(|(name, value)| !Record::is_extension_name(name) || value.is_empty())
+            .map(|(name, _)| name.as_str())
+    }
+
     pub fn from_draft(draft: &TextRecordDraft) -> Result<Record, ParseError> {
         Ok(Record {
             tx_id: draft.tx_id.ok_or(ParseError::MissingField("tx_id"))?,
@@ -40,20 +97,22 @@ impl Record {
                 .description
                 .clone()
                 .ok_or(ParseError::MissingField("description"))?,
+            extensions: draft.extensions.clone(),
         })
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct TextRecordDraft {
     pub tx_id: Option<u64>,
     pub tx_type: Option<TransactionType>,
     pub from_user_id: Option<u64>,
     pub to_user_id: Option<u64>,
-    pub amount: Option<u64>,
-    pub timestamp: Option<u64>,
+    pub amount: Option<Amount>,
+    pub timestamp: Option<Timestamp>,
     pub status: Option<TransactionStatus>,
     pub description: Option<String>,
+    pub extensions: BTreeMap<String, String>,
 }
 
 impl TextRecordDraft {
@@ -66,6 +125,7 @@ impl TextRecordDraft {
         self.timestamp = None;
         self.status = None;
         self.description = None;
+        self.extensions.clear();
     }
 
     pub fn is_empty(&self) -> bool {
@@ -77,6 +137,7 @@ impl TextRecordDraft {
             && self.timestamp.is_none()
             && self.status.is_none()
             && self.description.is_none()
+            && self.extensions.is_empty()
     }
 }
 
@@ -172,13 +233,89 @@ impl TransactionStatus {
     }
 }
 
-#[derive(Clone, Debug)]
+pub const BIN_MAGIC: [u8; 4] = *b"YPBN";
+/// Magic of bin records that end with a CRC32 checksum
+pub const BIN_MAGIC_V2: [u8; 4] = *b"YPB2";
+/// Magic of the optional header at the start of bin files
+pub const BIN_FILE_MAGIC: [u8; 4] = *b"YPBF";
+pub const CSV_HEADER: &str =
+    "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION";
+pub const TEXT_KEYS: [&str; 8] = [
+    "TX_ID",
+    "TX_TYPE",
+    "FROM_USER_ID",
+    "TO_USER_ID",
+    "AMOUNT",
+    "TIMESTAMP",
+    "STATUS",
+    "DESCRIPTION",
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Format {
     Csv,
     Text,
     Bin,
 }
 
+impl Format {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Text => "text",
+            Format::Bin => "bin",
+        }
+    }
+
+    /// Guess the format from the first bytes of an input
+    pub fn detect(sample: &[u8]) -> Result<Format, ParseError> {
+        // text formats never contain NUL bytes, bin record sizes always do
+        let text = if sample.contains(&0) {
+            Cow::Borrowed("")
+        } else {
+            String::from_utf8_lossy(sample)
+        };
+        let mut lines = text.lines().map(|l| l.trim()).filter(|l| !l.is_empty());
+
+        let is_bin = [BIN_FILE_MAGIC, BIN_MAGIC, BIN_MAGIC_V2]
+            .iter()
+            .any(|magic| sample.starts_with(magic));
+        // csv columns may come in any order, with more columns around them and in any of
+        // the usual dialects
+        let is_csv = lines.clone().next().is_some_and(|l| {
+            let columns: Vec<&str> = l
+                .trim_start_matches('\u{feff}')
+                .split([',', ';', '\t'])
+                .map(|c| c.trim().trim_matches('"'))
+                .collect();
+            TEXT_KEYS
+                .iter()
+                .all(|key| columns.iter().any(|c| c.eq_ignore_ascii_case(key)))
+        });
+        let is_text = lines.any(|l| {
+            l.split_once(':')
+                .is_some_and(|(key, _)| TEXT_KEYS.contains(&key.trim()))
+        });
+
+        let mut found: Vec<Format> = Vec::new();
+        if is_bin {
+            found.push(Format::Bin);
+        }
+        if is_csv {
+            found.push(Format::Csv);
+        }
+        if is_text {
+            found.push(Format::Text);
+        }
+
+        match found.len() {
+            0 => Err(ParseError::FormatNotDetected),
+            1 => Ok(found.remove(0)),
+            _ => Err(ParseError::AmbiguousFormat(found)),
+        }
+    }
+}
+
 impl Display for Format {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -189,6 +326,104 @@ impl Display for Format {
     }
 }
 
+/// Iterator over parsed records that knows the raw input of the last item
+///
+/// After an error the iterator goes on with the next record when the input allows it,
+/// otherwise it ends.
+pub trait RecordSource: Iterator<Item = Result<Record, ParseError>> {
+    fn raw(&self) -> &[u8];
+
+    /// Whether reading can go on after the last error, false when it lost the rest of the
+    /// input, e.g. after an I/O error or an unusable header
+    fn can_continue(&self) -> bool;
+}
+
+impl<S: RecordSource + ?Sized> RecordSource for Box<S> {
+    fn raw(&self) -> &[u8] {
+        (**self).raw()
+    }
+
+    fn can_continue(&self) -> bool {
+        (**self).can_continue()
+    }
+}
+
 pub trait Reader {
-    fn read_from(file: File) -> Result<Vec<Record>, ParseError>;
+    fn records<R: Read>(r: R) -> impl RecordSource;
+
+    fn read_from<R: Read>(r: R) -> Result<Vec<Record>, ParseError> {
+        Self::records(r).collect()
+    }
+
+    fn read_with<R: Read>(r: R, options: &ParseOptions) -> Result<ParseReport, ParseError> {
+        options.read(Self::records(r))
+    }
+}
+
+pub trait Writer {
+    fn writer<W: Write>(w: W) -> impl RecordWriter;
+
+    fn write_to<W: Write, I: IntoIterator<Item = Record>>(
+        w: &mut W,
+        records: I,
+    ) -> Result<(), ParseError> {
+        let mut writer = Self::writer(w);
+
+        for record in records {
+            writer.write_record(&record)?;
+        }
+
+        writer.finish()
+    }
+}
+
+pub trait RecordWriter {
+    fn write_record(&mut self, record: &Record) -> Result<(), ParseError>;
+    fn finish(&mut self) -> Result<(), ParseError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_format() {
+        let bin = [89, 80, 66, 78, 0, 0, 0, 63, 0, 3, 141, 126];
+        let csv = format!(
+            "{}\n1,DEPOSIT,0,1,100,1633036860000,FAILURE,x\n",
+            CSV_HEADER
+        );
+        let text = "# Record 1 (DEPOSIT)\nTX_TYPE: DEPOSIT\nTO_USER_ID: 92233";
+        let reordered =
+            "id,\"DESCRIPTION\",TX_TYPE,TX_ID,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS\n";
+
+        assert_eq!(Format::detect(&bin).unwrap(), Format::Bin);
+        assert_eq!(Format::detect(b"YPB2\0\0\0\x43").unwrap(), Format::Bin);
+        assert_eq!(Format::detect(b"YPBF\0\x01\0\x01").unwrap(), Format::Bin);
+        assert_eq!(Format::detect(csv.as_bytes()).unwrap(), Format::Csv);
+        assert_eq!(Format::detect(reordered.as_bytes()).unwrap(), Format::Csv);
+        assert_eq!(
+            Format::detect(reordered.replace(',', ";").as_bytes()).unwrap(),
+            Format::Csv
+        );
+        assert_eq!(Format::detect(text.as_bytes()).unwrap(), Format::Text);
+    }
+
+    #[test]
+    fn test_detect_format_errors() {
+        let both = format!("{}\nTX_ID: 1\n", CSV_HEADER);
+
+        assert!(matches!(
+            Format::detect(b""),
+            Err(ParseError::FormatNotDetected)
+        ));
+        assert!(matches!(
+            Format::detect(b"id;amount\n1;100"),
+            Err(ParseError::FormatNotDetected)
+        ));
+        assert!(matches!(
+            Format::detect(both.as_bytes()),
+            Err(ParseError::AmbiguousFormat(f)) if f == [Format::Csv, Format::Text]
+        ));
+    }
 }
@@ -0,0 +1,120 @@
+use crate::error::ParseError;
+use crate::model::{Record, RecordSource};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorStrategy {
+    /// Stop at the first broken record
+    #[default]
+    FailFast,
+    /// Drop broken records silently, errors that end the input are still returned
+    Skip,
+    /// Drop broken records and keep a diagnostic for each of them
+    Collect,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    pub on_error: ErrorStrategy,
+}
+
+/// A rejected record: why it was rejected and the raw input it was parsed from
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub error: ParseError,
+    pub raw: Vec<u8>,
+}
+
+#[derive(Debug, Default)]
+pub struct ParseReport {
+    pub records: Vec<Record>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ParseOptions {
+    pub fn new(on_error: ErrorStrategy) -> Self {
+        ParseOptions { on_error }
+    }
+
+    /// Wrap a source so broken records are handled according to the options
+    pub fn apply<S: RecordSource>(&self, source: S) -> Lenient<S> {
+        Lenient {
+            source,
+            on_error: self.on_error,
+            diagnostics: Vec::new(),
+            rejected: 0,
+            failed: false,
+        }
+    }
+
+    /// Read all records of a source, the error is only returned with [`ErrorStrategy::FailFast`]
+    /// or when the source can not go on after it
+    pub fn read<S: RecordSource>(&self, source: S) -> Result<ParseReport, ParseError> {
+        let mut lenient = self.apply(source);
+        let records = lenient.by_ref().collect::<Result<Vec<_>, _>>()?;
+
+        Ok(ParseReport {
+            records,
+            diagnostics: lenient.diagnostics,
+        })
+    }
+}
+
+/// Iterator over the good records of a source, see [`ParseOptions::apply`]
+pub struct Lenient<S> {
+    source: S,
+    on_error: ErrorStrategy,
+    diagnostics: Vec<Diagnostic>,
+    rejected: usize,
+    failed: bool,
+}
+
+impl<S> Lenient<S> {
+    /// Diagnostics collected so far with [`ErrorStrategy::Collect`]
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Remove and return the diagnostics collected so far
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    /// Number of records dropped so far
+    pub fn rejected(&self) -> usize {
+        self.rejected
+    }
+}
+
+impl<S: RecordSource> Iterator for Lenient<S> {
+    type Item = Result<Record, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        loop {
+            match self.source.next()? {
+                Ok(record) => return Some(Ok(record)),
+                Err(error) => match self.on_error {
+                    _ if !self.source.can_continue() => {
+                        self.failed = true;
+                        return Some(Err(error));
+                    }
+                    ErrorStrategy::FailFast => {
+                        self.failed = true;
+                        return Some(Err(error));
+                    }
+                    ErrorStrategy::Skip => self.rejected += 1,
+                    ErrorStrategy::Collect => {
+                        self.rejected += 1;
+                        self.diagnostics.push(Diagnostic {
+                            error,
+                            raw: self.source.raw().to_vec(),
+                        });
+                    }
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,302 @@
+use std::collections::HashSet;
+use std::fmt::Display;
+
+use crate::model::{Record, TransactionType};
+use crate::value::Amount;
+
+/// 2000-01-01T00:00:00Z in epoch milliseconds
+pub const MIN_TIMESTAMP: u64 = 946_684_800_000;
+/// 2100-01-01T00:00:00Z in epoch milliseconds
+pub const MAX_TIMESTAMP: u64 = 4_102_444_800_000;
+
+/// Business invariant a record has to hold
+///
+/// Rules see the records in input order, so they can keep state across records.
+pub trait Rule {
+    fn name(&self) -> &str;
+
+    /// Why `record` breaks the rule, `None` when it holds
+    fn check(&mut self, record: &Record) -> Option<String>;
+}
+
+/// DEPOSIT comes from outside, so it has no sender
+#[derive(Debug, Clone, Default)]
+pub struct DepositFromZero;
+
+impl Rule for DepositFromZero {
+    fn name(&self) -> &str {
+        "deposit-from-zero"
+    }
+
+    fn check(&mut self, record: &Record) -> Option<String> {
+        (record.tx_type == TransactionType::Deposit && record.from_user_id != 0)
+            .then(|| format!("DEPOSIT from user {}, expected 0", record.from_user_id))
+    }
+}
+
+/// WITHDRAWAL goes outside, so it has no receiver
+#[derive(Debug, Clone, Default)]
+pub struct WithdrawalToZero;
+
+impl Rule for WithdrawalToZero {
+    fn name(&self) -> &str {
+        "withdrawal-to-zero"
+    }
+
+    fn check(&mut self, record: &Record) -> Option<String> {
+        (record.tx_type == TransactionType::Withdrawal && record.to_user_id != 0)
+            .then(|| format!("WITHDRAWAL to user {}, expected 0", record.to_user_id))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PositiveAmount;
+
+impl Rule for PositiveAmount {
+    fn name(&self) -> &str {
+        "positive-amount"
+    }
+
+    fn check(&mut self, record: &Record) -> Option<String> {
+        (record.amount == Amount::ZERO).then(|| "amount is 0".to_string())
+    }
+}
+
+/// Timestamp within `min..=max` epoch milliseconds
+#[derive(Debug, Clone)]
+pub struct TimestampRange {
+    pub min: u64,
+    pub max: u64,
+}
+
+impl Default for TimestampRange {
+    fn default() -> Self {
+        TimestampRange {
+            min: MIN_TIMESTAMP,
+            max: MAX_TIMESTAMP,
+        }
+    }
+}
+
+impl Rule for TimestampRange {
+    fn name(&self) -> &str {
+        "timestamp-range"
+    }
+
+    fn check(&mut self, record: &Record) -> Option<String> {
+        (!(self.min..=self.max).contains(&record.timestamp.millis())).then(|| {
+            format!(
+                "timestamp {} is out of {}..={}",
+                record.timestamp, self.min, self.max
+            )
+        })
+    }
+}
+
+/// Every tx_id appears only once in the checked records
+#[derive(Debug, Clone, Default)]
+pub struct UniqueTxId {
+    seen: HashSet<u64>,
+}
+
+impl Rule for UniqueTxId {
+    fn name(&self) -> &str {
+        "unique-tx-id"
+    }
+
+    fn check(&mut self, record: &Record) -> Option<String> {
+        (!self.seen.insert(record.tx_id)).then(|| format!("tx_id {} is repeated", record.tx_id))
+    }
+}
+
+/// A record that broke a rule
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// 0-based index of the record in the checked input
+    pub record: usize,
+    pub tx_id: u64,
+    pub rule: String,
+    pub message: String,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "record {} (tx_id {}): {}: {}",
+            self.record, self.tx_id, self.rule, self.message
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    /// Number of checked records
+    pub records: usize,
+    pub violations: Vec<Violation>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Checks records against a set of rules, [`Validator::default`] has all built-in rules
+pub struct Validator {
+    rules: Vec<Box<dyn Rule>>,
+    index: usize,
+}
+
+impl Validator {
+    /// Validator without any rule
+    pub fn new() -> Self {
+        Validator {
+            rules: Vec::new(),
+            index: 0,
+        }
+    }
+
+    pub fn with_rule<R: Rule + 'static>(mut self, rule: R) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// Drop the rules named `name`
+    pub fn without(mut self, name: &str) -> Self {
+        self.rules.retain(|rule| rule.name() != name);
+        self
+    }
+
+    pub fn rule_names(&self) -> Vec<&str> {
+        self.rules.iter().map(|rule| rule.name()).collect()
+    }
+
+    /// Check the next record of the input
+    pub fn check(&mut self, record: &Record) -> Vec<Violation> {
+        let index = self.index;
+        self.index += 1;
+
+        self.rules
+            .iter_mut()
+            .filter_map(|rule| {
+                let message = rule.check(record)?;
+                Some(Violation {
+                    record: index,
+                    tx_id: record.tx_id,
+                    rule: rule.name().to_string(),
+                    message,
+                })
+            })
+            .collect()
+    }
+
+    pub fn validate<'a, I: IntoIterator<Item = &'a Record>>(
+        &mut self,
+        records: I,
+    ) -> ValidationReport {
+        let mut report = ValidationReport::default();
+
+        for record in records {
+            report.records += 1;
+            report.violations.extend(self.check(record));
+        }
+
+        report
+    }
+}
+
+impl Default for Validator {
+    fn default() -> Self {
+        Validator::new()
+            .with_rule(DepositFromZero)
+            .with_rule(WithdrawalToZero)
+            .with_rule(PositiveAmount)
+            .with_rule(TimestampRange::default())
+            .with_rule(UniqueTxId::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::TransactionStatus;
+
+    fn record(tx_id: u64, tx_type: TransactionType, from: u64, to: u64) -> Record {
+        Record {
+            tx_id,
+            tx_type,
+            from_user_id: from,
+            to_user_id: to,
+            amount: 100.into(),
+            timestamp: 1633036860000.into(),
+            status: TransactionStatus::Success,
+            description: "\"Record number 1\"".to_string(),
+            extensions: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_built_in_rules() {
+        let records = [
+            record(1, TransactionType::Deposit, 0, 7),
+            record(2, TransactionType::Deposit, 5, 7),
+            record(3, TransactionType::Withdrawal, 5, 7),
+            record(1, TransactionType::Transfer, 5, 7),
+            Record {
+                amount: Amount::ZERO,
+                timestamp: 10.into(),
+                ..record(4, TransactionType::Transfer, 5, 7)
+            },
+        ];
+
+        let report = Validator::default().validate(&records);
+        let broken: Vec<(usize, &str)> = report
+            .violations
+            .iter()
+            .map(|v| (v.record, v.rule.as_str()))
+            .collect();
+
+        assert_eq!(report.records, 5);
+        assert_eq!(
+            broken,
+            [
+                (1, "deposit-from-zero"),
+                (2, "withdrawal-to-zero"),
+                (3, "unique-tx-id"),
+                (4, "positive-amount"),
+                (4, "timestamp-range"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_configured_rules() {
+        struct NoSelfTransfer;
+
+        impl Rule for NoSelfTransfer {
+            fn name(&self) -> &str {
+                "no-self-transfer"
+            }
+
+            fn check(&mut self, record: &Record) -> Option<String> {
+                (record.from_user_id == record.to_user_id).then(|| "same user".to_string())
+            }
+        }
+
+        let mut validator = Validator::default()
+            .without("deposit-from-zero")
+            .with_rule(NoSelfTransfer);
+        let report = validator.validate(&[
+            record(1, TransactionType::Deposit, 5, 7),
+            record(2, TransactionType::Transfer, 7, 7),
+        ]);
+
+        assert!(!validator.rule_names().contains(&"deposit-from-zero"));
+        assert_eq!(report.violations.len(), 1);
+        assert_eq!(
+            report.violations[0].to_string(),
+            "record 1 (tx_id 2): no-self-transfer: same user"
+        );
+    }
+}
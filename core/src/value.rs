@@ -0,0 +1,517 @@
+use std::fmt::Display;
+
+/// Money in integer minor units, e.g. cents when amounts are written with 2 decimals
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Amount(u64);
+
+impl Amount {
+    pub const ZERO: Amount = Amount(0);
+
+    pub fn from_minor(minor: u64) -> Self {
+        Amount(minor)
+    }
+
+    pub fn minor(self) -> u64 {
+        self.0
+    }
+
+    /// Decimal representation with `scale` digits after the point, `1234` is `12.34` for scale 2
+    pub fn format(self, scale: u8) -> String {
+        let scale = scale as usize;
+        let digits = self.0.to_string();
+        if scale == 0 {
+            return digits;
+        }
+
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (units, fraction) = digits.split_at(digits.len() - scale);
+        format!("{}.{}", units, fraction)
+    }
+
+    /// Parse a decimal with at most `scale` significant digits after the point
+    ///
+    /// `12.3` and `12` are both read as `1230` minor units for scale 2.
+    pub fn parse(value: &str, scale: u8) -> Option<Amount> {
+        let scale = scale as usize;
+        let (units, fraction) = match value.split_once('.') {
+            Some((units, fraction)) if !fraction.is_empty() => (units, fraction),
+            Some(_) => return None,
+            None => (value, ""),
+        };
+
+        let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        if units.is_empty() || !is_digits(units) || !is_digits(fraction) {
+            return None;
+        }
+
+        let fraction = if fraction.len() > scale {
+            let (kept, rest) = fraction.split_at(scale);
+            rest.bytes().all(|b| b == b'0').then_some(kept)?
+        } else {
+            fraction
+        };
+
+        format!("{}{:0<scale$}", units, fraction)
+            .parse()
+            .ok()
+            .map(Amount)
+    }
+}
+
+impl From<u64> for Amount {
+    fn from(minor: u64) -> Self {
+        Amount(minor)
+    }
+}
+
+impl From<Amount> for u64 {
+    fn from(amount: Amount) -> Self {
+        amount.0
+    }
+}
+
+impl Display for Amount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+const MILLIS_PER_DAY: i64 = 86_400_000;
+/// 9999-12-31T00:00:00Z, later timestamps have no 4 digit year in every time zone
+const MAX_RFC3339: u64 = 253_402_214_400_000;
+
+/// Point in time as milliseconds since the Unix epoch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(u64);
+
+impl Timestamp {
+    pub fn from_millis(millis: u64) -> Self {
+        Timestamp(millis)
+    }
+
+    pub fn millis(self) -> u64 {
+        self.0
+    }
+
+    /// RFC 3339 representation in the time zone `offset_minutes` away from UTC
+    ///
+    /// Milliseconds are only written when there are any, `1633036860000` is
+    /// `2021-09-30T21:21:00Z` in UTC and `2021-10-01T00:21:00+03:00` with an offset of 180.
+    /// Local times outside the years 0000 to 9999 and offsets of a day or more have no
+    /// RFC 3339 spelling, they are written as epoch milliseconds, which are read as well.
+    pub fn to_rfc3339(self, offset_minutes: i32) -> String {
+        // 0000-01-01T00:00:00 and 10000-01-01T00:00:00
+        const LOCAL_RANGE: std::ops::Range<i64> = -62_167_219_200_000..253_402_300_800_000;
+
+        let local = i64::try_from(self.0)
+            .ok()
+            .and_then(|millis| millis.checked_add(offset_minutes as i64 * 60_000));
+        let local = match local {
+            Some(local)
+                if offset_minutes.unsigned_abs() < 24 * 60 && LOCAL_RANGE.contains(&local) =>
+            {
+                local
+            }
+            _ => return self.0.to_string(),
+        };
+        let (year, month, day) = civil_from_days(local.div_euclid(MILLIS_PER_DAY));
+        let time = local.rem_euclid(MILLIS_PER_DAY);
+        let (hour, minute) = (time / 3_600_000, time / 60_000 % 60);
+        let (second, millis) = (time / 1000 % 60, time % 1000);
+
+        let mut value = format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            year, month, day, hour, minute, second
+        );
+        if millis != 0 {
+            value.push_str(&format!(".{:03}", millis));
+        }
+        value.push_str(&format_utc_offset(offset_minutes));
+        value
+    }
+
+    /// Parse an RFC 3339 date and time like `2021-09-30T21:21:00.5+03:00`
+    ///
+    /// Digits of the seconds fraction after the milliseconds are dropped.
+    pub fn parse_rfc3339(value: &str) -> Option<Timestamp> {
+        let bytes = value.as_bytes();
+        if bytes.len() < 20
+            || bytes[4] != b'-'
+            || bytes[7] != b'-'
+            || !matches!(bytes[10], b'T' | b't' | b' ')
+            || bytes[13] != b':'
+            || bytes[16] != b':'
+        {
+            return None;
+        }
+
+        let number = |range: std::ops::Range<usize>| -> Option<i64> {
+            let digits = value.get(range)?;
+            digits
+                .bytes()
+                .all(|b| b.is_ascii_digit())
+                .then(|| digits.parse().ok())?
+        };
+        let (year, month, day) = (number(0..4)?, number(5..7)?, number(8..10)?);
+        let (hour, minute, second) = (number(11..13)?, number(14..16)?, number(17..19)?);
+
+        if !(1..=12).contains(&month)
+            || !(1..=days_in_month(year, month)).contains(&day)
+            || hour > 23
+            || minute > 59
+            || second > 60
+        {
+            return None;
+        }
+
+        let mut rest = &value[19..];
+        let mut millis = 0;
+        if let Some(fraction) = rest.strip_prefix('.') {
+            let digits = fraction.bytes().take_while(|b| b.is_ascii_digit()).count();
+            if digits == 0 {
+                return None;
+            }
+            millis = format!("{:0<3}", &fraction[..digits.min(3)]).parse().ok()?;
+            rest = &fraction[digits..];
+        }
+        let offset = parse_utc_offset(rest)?;
+
+        let millis = days_from_civil(year, month, day) * MILLIS_PER_DAY
+            + ((hour * 60 + minute - offset as i64) * 60 + second) * 1000
+            + millis;
+        u64::try_from(millis).ok().map(Timestamp)
+    }
+
+    /// Parse a date and time spelled by `pattern` in the time zone `offset_minutes` away from UTC
+    ///
+    /// `YYYY` stands for the year, `MM`, `DD`, `hh`, `mm` and `ss` for two digits of
+    /// the month, day, hour, minute and second, any other character for itself.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::Timestamp;
+    ///
+    /// let timestamp = Timestamp::parse_pattern("01.10.2021", "DD.MM.YYYY", 0).unwrap();
+    ///
+    /// assert_eq!(timestamp.to_rfc3339(0), "2021-10-01T00:00:00Z");
+    /// assert!(Timestamp::parse_pattern("1.10.2021", "DD.MM.YYYY", 0).is_none());
+    /// ```
+    pub fn parse_pattern(value: &str, pattern: &str, offset_minutes: i32) -> Option<Timestamp> {
+        // year, month, day, hour, minute, second
+        let mut parts = [1970, 1, 1, 0, 0, 0];
+        let (mut value, mut pattern) = (value, pattern);
+
+        while !pattern.is_empty() {
+            let token = ["YYYY", "MM", "DD", "hh", "mm", "ss"]
+                .iter()
+                .position(|token| pattern.starts_with(token));
+            match token {
+                Some(index) => {
+                    let width = if index == 0 { 4 } else { 2 };
+                    let digits = value.get(..width)?;
+                    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+                        return None;
+                    }
+                    parts[index] = digits.parse().ok()?;
+                    (value, pattern) = (&value[width..], &pattern[width..]);
+                }
+                None => {
+                    let c = pattern.chars().next()?;
+                    value = value.strip_prefix(c)?;
+                    pattern = &pattern[c.len_utf8()..];
+                }
+            }
+        }
+
+        let [year, month, day, hour, minute, second] = parts;
+        if !value.is_empty()
+            || !(1..=12).contains(&month)
+            || !(1..=days_in_month(year, month)).contains(&day)
+            || hour > 23
+            || minute > 59
+            || second > 59
+        {
+            return None;
+        }
+
+        let millis = days_from_civil(year, month, day) * MILLIS_PER_DAY
+            + ((hour * 60 + minute - offset_minutes as i64) * 60 + second) * 1000;
+        u64::try_from(millis).ok().map(Timestamp)
+    }
+
+    /// Parse either epoch milliseconds or an RFC 3339 date and time
+    pub fn parse(value: &str) -> Option<Timestamp> {
+        if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) {
+            return value.parse().ok().map(Timestamp);
+        }
+        Timestamp::parse_rfc3339(value)
+    }
+}
+
+impl From<u64> for Timestamp {
+    fn from(millis: u64) -> Self {
+        Timestamp(millis)
+    }
+}
+
+impl From<Timestamp> for u64 {
+    fn from(timestamp: Timestamp) -> Self {
+        timestamp.0
+    }
+}
+
+impl Display for Timestamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Parse a UTC offset `Z`, `+HH:MM` or `-HH:MM`, with exactly two digits each, into minutes
+pub fn parse_utc_offset(value: &str) -> Option<i32> {
+    if value.eq_ignore_ascii_case("z") {
+        return Some(0);
+    }
+
+    let (sign, rest) = match value.as_bytes().first()? {
+        b'+' => (1, &value[1..]),
+        b'-' => (-1, &value[1..]),
+        _ => return None,
+    };
+    let (hours, minutes) = rest.split_once(':')?;
+    let two_digits = |part: &str| part.len() == 2 && part.bytes().all(|b| b.is_ascii_digit());
+    if !two_digits(hours) || !two_digits(minutes) {
+        return None;
+    }
+    let (hours, minutes): (i32, i32) = (hours.parse().ok()?, minutes.parse().ok()?);
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+
+    Some(sign * (hours * 60 + minutes))
+}
+
+fn format_utc_offset(offset_minutes: i32) -> String {
+    if offset_minutes == 0 {
+        return "Z".to_string();
+    }
+
+    let sign = if offset_minutes < 0 { '-' } else { '+' };
+    let offset = offset_minutes.abs();
+    format!("{}{:02}:{:02}", sign, offset / 60, offset % 60)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// days since 1970-01-01 of a proleptic Gregorian date, see
+// http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+
+    (year_of_era + era * 400 + i64::from(month <= 2), month, day)
+}
+
+/// How the text based formats write timestamps, both spellings are always accepted on read
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// Epoch milliseconds
+    #[default]
+    Millis,
+    /// RFC 3339 in the time zone `offset_minutes` away from UTC
+    Rfc3339 { offset_minutes: i32 },
+}
+
+impl TimestampFormat {
+    /// Timestamps after year 9999 are always written as milliseconds, RFC 3339 can not hold them
+    pub fn format(&self, timestamp: Timestamp) -> String {
+        match self {
+            TimestampFormat::Rfc3339 { offset_minutes } if timestamp.millis() < MAX_RFC3339 => {
+                timestamp.to_rfc3339(*offset_minutes)
+            }
+            _ => timestamp.to_string(),
+        }
+    }
+}
+
+/// What quotes around descriptions mean in the text based formats
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DescriptionQuotes {
+    /// Quotes are syntax, a description holds the unquoted value
+    #[default]
+    Syntax,
+    /// Quotes are part of the description, as in files written before quoting was defined
+    Legacy,
+}
+
+/// How the text based formats spell values
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValueFormat {
+    /// Number of decimals of amounts, 0 keeps them as integer minor units
+    pub amount_scale: u8,
+    pub timestamp: TimestampFormat,
+    pub description: DescriptionQuotes,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_amount_format() {
+        assert_eq!(Amount::from(1234).format(0), "1234");
+        assert_eq!(Amount::from(1234).format(2), "12.34");
+        assert_eq!(Amount::from(5).format(2), "0.05");
+        assert_eq!(Amount::ZERO.format(3), "0.000");
+    }
+
+    #[test]
+    fn test_amount_parse() {
+        assert_eq!(Amount::parse("12.34", 2), Some(Amount::from(1234)));
+        assert_eq!(Amount::parse("12.3", 2), Some(Amount::from(1230)));
+        assert_eq!(Amount::parse("12", 2), Some(Amount::from(1200)));
+        assert_eq!(Amount::parse("12.00", 0), Some(Amount::from(12)));
+        assert_eq!(Amount::parse("100", 0), Some(Amount::from(100)));
+
+        assert_eq!(Amount::parse("12.345", 2), None);
+        assert_eq!(Amount::parse("12.34", 0), None);
+        assert_eq!(Amount::parse("-1", 2), None);
+        assert_eq!(Amount::parse(".5", 2), None);
+        assert_eq!(Amount::parse("5.", 2), None);
+        assert_eq!(Amount::parse("1,5", 2), None);
+        assert_eq!(Amount::parse("18446744073709551616", 0), None);
+    }
+
+    #[test]
+    fn test_timestamp_rfc3339() {
+        let timestamp = Timestamp::from(1633036860000);
+
+        assert_eq!(timestamp.to_rfc3339(0), "2021-09-30T21:21:00Z");
+        assert_eq!(timestamp.to_rfc3339(180), "2021-10-01T00:21:00+03:00");
+        assert_eq!(timestamp.to_rfc3339(-90), "2021-09-30T19:51:00-01:30");
+        assert_eq!(Timestamp::from(1).to_rfc3339(0), "1970-01-01T00:00:00.001Z");
+        assert_eq!(
+            Timestamp::from(951782400000).to_rfc3339(0),
+            "2000-02-29T00:00:00Z"
+        );
+        assert_eq!(
+            Timestamp::from(0).to_rfc3339(-60),
+            "1969-12-31T23:00:00-01:00"
+        );
+        assert_eq!(
+            Timestamp::from(253402300799999).to_rfc3339(0),
+            "9999-12-31T23:59:59.999Z"
+        );
+        for (millis, offset) in [
+            (u64::MAX, 0),
+            (i64::MAX as u64 + 1, 0),
+            (i64::MAX as u64, i32::MAX),
+            (253402300800000, 0),
+            (1633036860000, 24 * 60),
+            (1633036860000, i32::MIN),
+            (0, i32::MIN),
+        ] {
+            let value = Timestamp::from(millis).to_rfc3339(offset);
+            assert_eq!(value, millis.to_string());
+            assert_eq!(Timestamp::parse(&value), Some(Timestamp::from(millis)));
+        }
+
+        for value in [
+            "2021-09-30T21:21:00Z",
+            "2021-10-01T00:21:00+03:00",
+            "2021-09-30 19:51:00-01:30",
+            "2021-09-30t21:21:00.000000z",
+        ] {
+            assert_eq!(
+                Timestamp::parse_rfc3339(value),
+                Some(timestamp),
+                "{}",
+                value
+            );
+        }
+        assert_eq!(
+            Timestamp::parse_rfc3339("1970-01-01T00:00:00.0019Z"),
+            Some(Timestamp::from(1))
+        );
+        assert_eq!(Timestamp::parse("1633036860000"), Some(timestamp));
+    }
+
+    #[test]
+    fn test_timestamp_parse_errors() {
+        for value in [
+            "2021-09-30",
+            "2021-09-30T21:21:00",
+            "2021-02-29T00:00:00Z",
+            "2021-13-01T00:00:00Z",
+            "2021-09-30T24:00:00Z",
+            "2021-09-30T21:21:00.Z",
+            "2021-09-30T21:21:00+3:00",
+            "1969-12-31T23:59:59Z",
+            "+021-09-30T21:21:00Z",
+            "2021-09-30T21:21:00++1:00",
+            "2021-09-30T21:21:00+-1:00",
+            "-5",
+            "+1633036860000",
+        ] {
+            assert_eq!(Timestamp::parse(value), None, "{}", value);
+        }
+        for value in [
+            "++1:00", "+-1:00", "-0:00", "+1:00", "+01:0", "+0a:00", "01:00",
+        ] {
+            assert_eq!(parse_utc_offset(value), None, "{}", value);
+        }
+        assert_eq!(parse_utc_offset("-01:30"), Some(-90));
+    }
+
+    #[test]
+    fn test_timestamp_pattern() {
+        let parse = |value, pattern| Timestamp::parse_pattern(value, pattern, 0);
+
+        assert_eq!(
+            parse("30.09.2021 21:21:00", "DD.MM.YYYY hh:mm:ss"),
+            Some(Timestamp::from(1633036860000))
+        );
+        assert_eq!(
+            Timestamp::parse_pattern("01.10.2021 00:21", "DD.MM.YYYY hh:mm", 180),
+            Some(Timestamp::from(1633036860000))
+        );
+        assert_eq!(
+            parse("2000/02/29", "YYYY/MM/DD"),
+            Some(Timestamp::from(951782400000))
+        );
+
+        assert_eq!(parse("29.02.2021", "DD.MM.YYYY"), None);
+        assert_eq!(parse("30.09.21", "DD.MM.YYYY"), None);
+        assert_eq!(parse("30-09-2021", "DD.MM.YYYY"), None);
+        assert_eq!(parse("30.09.2021 ", "DD.MM.YYYY"), None);
+        assert_eq!(parse("+1.09.2021", "DD.MM.YYYY"), None);
+    }
+}
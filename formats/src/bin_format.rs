@@ -1,18 +1,70 @@
 pub mod bin_parser {
-    use core::{ParseError, Reader, Record, TransactionStatus, TransactionType};
-    use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+    use core::{
+        Amount, BIN_FILE_MAGIC, BIN_MAGIC, BIN_MAGIC_V2, ParseError, ParseOptions, ParseReport,
+        Position, Reader, Record, RecordSource, RecordWriter, Timestamp, TransactionStatus,
+        TransactionType, Writer,
+    };
+    use std::collections::{BTreeMap, VecDeque};
+    use std::io::{BufReader, BufWriter, Read, Write};
+    use std::ops::Range;
 
     pub struct Bin;
 
     impl Reader for Bin {
-        fn read_from(file: std::fs::File) -> Result<Vec<Record>, ParseError> {
-            self::read_from(file)
+        fn records<R: std::io::Read>(r: R) -> impl RecordSource {
+            self::records(r)
+        }
+    }
+
+    impl Writer for Bin {
+        fn writer<W: std::io::Write>(w: W) -> impl RecordWriter {
+            BinWriter::new(w)
         }
     }
 
-    const MAGIC: [u8; 4] = *b"YPBN";
     const MIN_RECORD_SIZE: u32 = 46;
-    const MAX_RECORD_SIZE: u32 = 150;
+    /// Room for long descriptions and extension fields. Resync reads this much only for
+    /// candidates whose fixed fields are plausible, see [`plausible`]
+    const MAX_RECORD_SIZE: u32 = 64 * 1024;
+
+    /// Revision of the records a writer produces, readers accept both even mixed in one file
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub enum BinVersion {
+        /// `YPBN` records without a checksum
+        #[default]
+        V1,
+        /// `YPB2` records ending with a big endian CRC-32 of all their other bytes,
+        /// counted in the record size
+        V2,
+    }
+
+    impl BinVersion {
+        fn of(magic: &[u8]) -> Option<BinVersion> {
+            match magic.get(..4)? {
+                magic if magic == BIN_MAGIC => Some(BinVersion::V1),
+                magic if magic == BIN_MAGIC_V2 => Some(BinVersion::V2),
+                _ => None,
+            }
+        }
+
+        fn magic(self) -> [u8; 4] {
+            match self {
+                BinVersion::V1 => BIN_MAGIC,
+                BinVersion::V2 => BIN_MAGIC_V2,
+            }
+        }
+
+        fn checksum_len(self) -> usize {
+            match self {
+                BinVersion::V1 => 0,
+                BinVersion::V2 => 4,
+            }
+        }
+
+        fn record_sizes(self) -> std::ops::RangeInclusive<u32> {
+            MIN_RECORD_SIZE + self.checksum_len() as u32..=MAX_RECORD_SIZE
+        }
+    }
 
     /// Read transactions from binary format and converting to Record entity
     ///
@@ -31,72 +83,596 @@ pub mod bin_parser {
     /// assert_eq!(r.len(), 1);
     /// ```
     pub fn read_from<R: std::io::Read>(r: R) -> Result<Vec<Record>, ParseError> {
-        let mut data: Vec<Record> = Vec::new();
-        let mut reader = BufReader::new(r);
-        let mut header = [0u8; 8];
+        records(r).collect()
+    }
+
+    /// Lazily read transactions from binary format one Record at a time
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// const BYTES_MOCK: [u8; 71] = [
+    /// 89, 80, 66, 78, 0, 0, 0, 63, 0, 3, 141, 126, 164, 198, 128, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    /// 127, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 0, 0, 0, 0, 100, 0, 0, 1, 124, 56, 148,
+    /// 250, 96, 1, 0, 0, 0, 17, 34, 82, 101, 99, 111, 114, 100, 32, 110, 117, 109, 98, 101, 114,
+    /// 32, 49, 34];
+    ///
+    /// let cursor = std::io::Cursor::new(&BYTES_MOCK[..]);
+    /// let mut it = formats::bin_format::bin_parser::records(cursor);
+    ///
+    /// assert_eq!(it.next().unwrap().unwrap().amount.minor(), 100);
+    /// assert!(it.next().is_none());
+    /// ```
+    pub fn records<R: std::io::Read>(r: R) -> BinRecords<R> {
+        records_with(r, &BinOptions::default())
+    }
+
+    /// Optional header at the start of a bin file, files without one start with their
+    /// first record
+    ///
+    /// It is the [`BIN_FILE_MAGIC`] followed by the version, the flags and the creation
+    /// time in epoch milliseconds, all big endian.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct BinHeader {
+        pub version: u16,
+        /// Bit set of [`BinHeader::CHECKSUMS`], unknown bits are ignored. Records of a
+        /// different version than the flags name are [`ParseError::HeaderMismatch`]
+        pub flags: u16,
+        pub created: Timestamp,
+    }
+
+    impl BinHeader {
+        /// Latest version of the file layout, files with a later one are not read
+        pub const VERSION: u16 = 1;
+        /// The records of the file are written in [`BinVersion::V2`]
+        pub const CHECKSUMS: u16 = 1;
+        const SIZE: usize = 16;
+
+        /// Header of a file written now with records in `version`
+        pub fn new(version: BinVersion) -> BinHeader {
+            let created = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_millis() as u64);
+            BinHeader {
+                version: BinHeader::VERSION,
+                flags: match version {
+                    BinVersion::V1 => 0,
+                    BinVersion::V2 => BinHeader::CHECKSUMS,
+                },
+                created: Timestamp::from_millis(created),
+            }
+        }
+
+        /// Version of the records of the file
+        pub fn record_version(&self) -> BinVersion {
+            match self.flags & BinHeader::CHECKSUMS {
+                0 => BinVersion::V1,
+                _ => BinVersion::V2,
+            }
+        }
+
+        fn encode(&self) -> Vec<u8> {
+            let mut bytes = Vec::with_capacity(BinHeader::SIZE);
+            bytes.extend_from_slice(&BIN_FILE_MAGIC);
+            bytes.extend_from_slice(&self.version.to_be_bytes());
+            bytes.extend_from_slice(&self.flags.to_be_bytes());
+            bytes.extend_from_slice(&self.created.millis().to_be_bytes());
+            bytes
+        }
+
+        /// Header from all of its bytes
+        fn decode(bytes: &[u8]) -> Result<BinHeader, ParseError> {
+            let header = BinHeader {
+                version: u16::from_be_bytes([bytes[4], bytes[5]]),
+                flags: u16::from_be_bytes([bytes[6], bytes[7]]),
+                created: Timestamp::from_millis(self::u64(bytes, 8, 16)?),
+            };
+            if !(1..=BinHeader::VERSION).contains(&header.version) {
+                return Err(ParseError::UnsupportedVersion(header.version));
+            }
+            Ok(header)
+        }
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct BinOptions {
+        /// After a corrupted record scan forward for the next valid one instead of stopping
+        pub resync: bool,
+        /// Revision of written records
+        pub version: BinVersion,
+        /// Start written files with a [`BinHeader`]
+        pub header: bool,
+    }
+
+    /// Lazily read transactions from binary format with the given options
+    ///
+    /// With `resync` the bytes between a corrupted record and the next valid one are
+    /// reported as a [`ParseError::CorruptedBytes`] error and reading goes on.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// const BYTES_MOCK: [u8; 74] = [
+    /// 1, 2, 3, 89, 80, 66, 78, 0, 0, 0, 63, 0, 3, 141, 126, 164, 198, 128, 0, 0, 0, 0, 0, 0, 0,
+    /// 0, 0, 0, 127, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 0, 0, 0, 0, 100, 0, 0, 1, 124,
+    /// 56, 148, 250, 96, 1, 0, 0, 0, 17, 34, 82, 101, 99, 111, 114, 100, 32, 110, 117, 109, 98,
+    /// 101, 114, 32, 49, 34];
+    ///
+    /// let options = formats::bin_format::bin_parser::BinOptions {
+    ///     resync: true,
+    ///     ..Default::default()
+    /// };
+    /// let cursor = std::io::Cursor::new(&BYTES_MOCK[..]);
+    /// let mut it = formats::bin_format::bin_parser::records_with(cursor, &options);
+    ///
+    /// assert!(it.next().unwrap().is_err());
+    /// assert_eq!(it.next().unwrap().unwrap().amount.minor(), 100);
+    /// assert_eq!(it.skipped(), [0..3]);
+    /// ```
+    pub fn records_with<R: std::io::Read>(r: R, options: &BinOptions) -> BinRecords<R> {
+        BinRecords {
+            reader: Rewind {
+                inner: BufReader::new(r),
+                pending: VecDeque::new(),
+            },
+            options: options.clone(),
+            header: None,
+            started: false,
+            raw: Vec::new(),
+            resynced: None,
+            failure: None,
+            skipped: Vec::new(),
+            offset: 0,
+            index: 0,
+            done: false,
+            fatal: false,
+        }
+    }
+
+    /// Read transactions from binary format handling broken records according to `options`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// const BYTES_MOCK: [u8; 71] = [
+    /// 89, 80, 66, 78, 0, 0, 0, 63, 0, 3, 141, 126, 164, 198, 128, 0, 7, 0, 0, 0, 0, 0, 0, 0, 0,
+    /// 127, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 0, 0, 0, 0, 100, 0, 0, 1, 124, 56, 148,
+    /// 250, 96, 1, 0, 0, 0, 17, 34, 82, 101, 99, 111, 114, 100, 32, 110, 117, 109, 98, 101, 114,
+    /// 32, 49, 34];
+    ///
+    /// let options = formats::ParseOptions::new(formats::ErrorStrategy::Collect);
+    /// let cursor = std::io::Cursor::new(&BYTES_MOCK[..]);
+    /// let r = formats::bin_format::bin_parser::read_with(cursor, &options).unwrap();
+    ///
+    /// assert_eq!(r.records.len(), 0);
+    /// assert_eq!(r.diagnostics.len(), 1);
+    /// ```
+    pub fn read_with<R: std::io::Read>(
+        r: R,
+        options: &ParseOptions,
+    ) -> Result<ParseReport, ParseError> {
+        options.read(records(r))
+    }
 
-        loop {
-            match reader.read_exact(&mut header) {
-                Ok(_) => {}
+    /// Iterator over the records of a binary stream, see [`records`]
+    pub struct BinRecords<R: std::io::Read> {
+        reader: Rewind<R>,
+        options: BinOptions,
+        header: Option<BinHeader>,
+        started: bool,
+        raw: Vec<u8>,
+        resynced: Option<(Record, Vec<u8>)>,
+        /// Error that ended a resync, returned after the bytes skipped before it
+        failure: Option<ParseError>,
+        skipped: Vec<Range<u64>>,
+        offset: u64,
+        index: usize,
+        done: bool,
+        /// The last error ended the reading
+        fatal: bool,
+    }
+
+    impl<R: std::io::Read> Iterator for BinRecords<R> {
+        type Item = Result<Record, ParseError>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            if let Some((record, raw)) = self.resynced.take() {
+                self.raw = raw;
+                return Some(Ok(record));
+            }
+            if let Some(error) = self.failure.take() {
+                self.raw.clear();
+                self.fatal = true;
+                return Some(Err(error));
+            }
+            if self.done {
+                return None;
+            }
+            if !self.started {
+                self.started = true;
+                if let Err(e) = self.read_header() {
+                    (self.done, self.fatal) = (true, true);
+                    return Some(Err(e.at(Position::Byte {
+                        offset: 0,
+                        record: 0,
+                    })));
+                }
+            }
+
+            let start = self.offset;
+            let position = Position::Byte {
+                offset: self.offset,
+                record: self.index,
+            };
+            let next = match self.read_raw() {
+                Ok(true) => self::decode(&self.raw),
+                Ok(false) => return None,
+                Err(e @ (ParseError::Io(_) | ParseError::UnexpectedEof { .. })) => {
+                    (self.done, self.fatal) = (true, true);
+                    Err(e)
+                }
                 Err(e) => {
-                    if e.kind() == ErrorKind::UnexpectedEof {
+                    // without a valid header the start of the next record is unknown
+                    self.done = !self.options.resync;
+                    self.fatal = self.done;
+                    Err(e)
+                }
+            };
+
+            match next {
+                Err(_) if self.options.resync && !self.done => {
+                    Some(self.resync(start).map_err(|e| e.at(position)))
+                }
+                next => Some(next.map_err(|e| e.at(position))),
+            }
+        }
+    }
+
+    impl<R: std::io::Read> RecordSource for BinRecords<R> {
+        fn raw(&self) -> &[u8] {
+            &self.raw
+        }
+
+        fn can_continue(&self) -> bool {
+            !self.fatal
+        }
+    }
+
+    /// Outcome of checking the bytes at the current position for a valid record
+    enum Candidate {
+        Valid(Record, Vec<u8>),
+        Invalid(Vec<u8>),
+        Eof(Vec<u8>),
+    }
+
+    impl<R: std::io::Read> BinRecords<R> {
+        /// Byte ranges skipped so far while resynchronising
+        pub fn skipped(&self) -> &[Range<u64>] {
+            &self.skipped
+        }
+
+        /// Header of the file, known once the first record was read
+        pub fn header(&self) -> Option<&BinHeader> {
+            self.header.as_ref()
+        }
+
+        /// Read the file header if there is one, legacy files start with a record
+        fn read_header(&mut self) -> Result<(), ParseError> {
+            let mut bytes = Vec::with_capacity(BinHeader::SIZE);
+            self.reader.fill(&mut bytes, BIN_FILE_MAGIC.len())?;
+            if bytes != BIN_FILE_MAGIC {
+                self.reader.unread(&bytes);
+                return Ok(());
+            }
+
+            let got = bytes.len() + self.reader.fill(&mut bytes, BinHeader::SIZE - 4)?;
+            if got < BinHeader::SIZE {
+                self.raw = bytes;
+                return Err(ParseError::UnexpectedEof {
+                    needed: BinHeader::SIZE,
+                    got,
+                });
+            }
+            self.header = Some(BinHeader::decode(&bytes)?);
+            self.offset = BinHeader::SIZE as u64;
+            Ok(())
+        }
+
+        /// Skip bytes starting at `start`, where the record in `raw` failed, up to the next
+        /// record that decodes successfully and report the skipped bytes as an error
+        fn resync(&mut self, start: u64) -> Result<Record, ParseError> {
+            let failed = std::mem::take(&mut self.raw);
+            let mut skipped = vec![failed[0]];
+            self.reader.unread(&failed[1..]);
+
+            loop {
+                let candidate = match self.candidate() {
+                    Ok(candidate) => candidate,
+                    Err(e) => {
+                        self.offset = start + skipped.len() as u64;
+                        self.done = true;
+                        self.failure = Some(e.at(Position::Byte {
+                            offset: self.offset,
+                            record: self.index,
+                        }));
+                        break;
+                    }
+                };
+                match candidate {
+                    Candidate::Valid(record, raw) => {
+                        self.offset = start + (skipped.len() + raw.len()) as u64;
+                        self.resynced = Some((record, raw));
+                        break;
+                    }
+                    Candidate::Invalid(raw) => {
+                        skipped.push(raw[0]);
+                        self.reader.unread(&raw[1..]);
+                    }
+                    Candidate::Eof(raw) => {
+                        skipped.extend_from_slice(&raw);
+                        self.offset = start + skipped.len() as u64;
+                        self.done = true;
                         break;
-                    } else {
-                        return Err(ParseError::Io(e));
                     }
                 }
-            };
+            }
 
-            let magic = &header[0..4];
+            let end = start + skipped.len() as u64;
+            self.skipped.push(start..end);
+            self.raw = skipped;
+            Err(ParseError::CorruptedBytes { start, end })
+        }
 
-            if magic != MAGIC {
-                return Err(ParseError::InvalidMagic);
+        fn candidate(&mut self) -> Result<Candidate, ParseError> {
+            let mut raw = Vec::with_capacity(8);
+
+            if self.reader.fill(&mut raw, 1)? < 1 {
+                return Ok(Candidate::Eof(raw));
+            }
+            if raw[0] != BIN_MAGIC[0] {
+                return Ok(Candidate::Invalid(raw));
+            }
+            if self.reader.fill(&mut raw, 7)? < 7 {
+                return Ok(Candidate::Eof(raw));
             }
 
-            let mut rs_bytes = [0u8; 4];
-            rs_bytes.copy_from_slice(&header[4..8]);
-            let record_size = u32::from_be_bytes(rs_bytes);
+            let record_size = self::u32(&raw, 4, 8)?;
+            if !BinVersion::of(&raw)
+                .is_some_and(|v| v.record_sizes().contains(&record_size) && self.expects(v))
+            {
+                return Ok(Candidate::Invalid(raw));
+            }
+            let fixed = MIN_RECORD_SIZE as usize;
+            if self.reader.fill(&mut raw, fixed)? < fixed {
+                return Ok(Candidate::Eof(raw));
+            }
+            if !self::plausible(&raw) {
+                return Ok(Candidate::Invalid(raw));
+            }
+            let rest = record_size as usize - fixed;
+            if self.reader.fill(&mut raw, rest)? < rest {
+                return Ok(Candidate::Eof(raw));
+            }
+
+            Ok(match self::decode(&raw) {
+                Ok(record) => {
+                    self.index += 1;
+                    Candidate::Valid(record, raw)
+                }
+                Err(_) => Candidate::Invalid(raw),
+            })
+        }
+
+        /// Read the next record into `raw`, false at the end of the input
+        ///
+        /// Input that ends inside a record is [`ParseError::UnexpectedEof`] with the bytes
+        /// the record needs and the bytes that were left, counting its header.
+        fn read_raw(&mut self) -> Result<bool, ParseError> {
+            self.raw.clear();
+
+            let got = self.fill_raw(8)?;
+            if got == 0 {
+                self.done = true;
+                return Ok(false);
+            }
+            if got < 8 {
+                self.done = true;
+                return Err(ParseError::UnexpectedEof { needed: 8, got });
+            }
+
+            let version = BinVersion::of(&self.raw).ok_or(ParseError::InvalidMagic)?;
+            if !self.expects(version) {
+                return Err(ParseError::HeaderMismatch);
+            }
+            let record_size = self::u32(&self.raw, 4, 8)?;
 
-            if !(MIN_RECORD_SIZE..=MAX_RECORD_SIZE).contains(&record_size) {
+            if !version.record_sizes().contains(&record_size) {
                 return Err(ParseError::UnexpectedRecordSize(record_size));
             }
 
-            let mut body = vec![0u8; record_size as usize];
+            let needed = record_size as usize;
+            let got = self.fill_raw(needed)?;
+            if got < needed {
+                self.done = true;
+                return Err(ParseError::UnexpectedEof {
+                    needed: 8 + needed,
+                    got: 8 + got,
+                });
+            }
 
-            let _ = reader.read_exact(&mut body);
+            self.offset += 8 + record_size as u64;
+            self.index += 1;
 
-            let tx_id = self::u64(&body, 0, 8)?;
-            let tx_type = TransactionType::from_byte(self::u8(&body, 8)?)?;
-            let from_user_id = self::u64(&body, 9, 17)?;
-            let to_user_id = self::u64(&body, 17, 25)?;
-            let amount = self::u64(&body, 25, 33)?;
-            let timestamp = self::u64(&body, 33, 41)?;
-            let status = TransactionStatus::from_byte(self::u8(&body, 41)?)?;
-            let desc_len = self::u32(&body, 42, 46)?;
+            Ok(true)
+        }
+
+        /// Whether records in `version` belong in the file, any do without a header
+        fn expects(&self, version: BinVersion) -> bool {
+            self.header
+                .is_none_or(|header| header.record_version() == version)
+        }
 
-            if desc_len != record_size - MIN_RECORD_SIZE {
-                return Err(ParseError::RecordDamaged(tx_id));
+        /// Append up to `n` bytes of the input to `raw`, errors end the reading
+        fn fill_raw(&mut self, n: usize) -> Result<usize, ParseError> {
+            self.reader.fill(&mut self.raw, n).map_err(|e| {
+                self.done = true;
+                ParseError::Io(e)
+            })
+        }
+    }
+
+    /// Reader that can put bytes back to be read again
+    struct Rewind<R: std::io::Read> {
+        inner: BufReader<R>,
+        pending: VecDeque<u8>,
+    }
+
+    impl<R: std::io::Read> Rewind<R> {
+        fn unread(&mut self, bytes: &[u8]) {
+            for byte in bytes.iter().rev() {
+                self.pending.push_front(*byte);
             }
+        }
 
-            let desc_bytes = body.get(46..).ok_or(ParseError::RecordTooShort)?;
+        /// Append up to `n` bytes to `buf`, fewer only at the end of the input
+        fn fill(&mut self, buf: &mut Vec<u8>, n: usize) -> std::io::Result<usize> {
+            let read = Read::by_ref(self).take(n as u64).read_to_end(buf)?;
+            Ok(read)
+        }
+    }
 
-            let description =
-                String::from_utf8(desc_bytes.to_vec()).map_err(ParseError::InvalidUtf8)?;
+    impl<R: std::io::Read> Read for Rewind<R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.pending.is_empty() {
+                return self.inner.read(buf);
+            }
 
-            let record = Record {
-                tx_id,
-                tx_type,
-                from_user_id,
-                to_user_id,
-                amount,
-                timestamp,
-                description,
-                status,
-            };
-            data.push(record);
+            let n = buf.len().min(self.pending.len());
+            for (slot, byte) in buf.iter_mut().zip(self.pending.drain(..n)) {
+                *slot = byte;
+            }
+            Ok(n)
         }
+    }
+
+    /// Whether the fixed fields at the start of the record in `raw` can belong to a valid
+    /// record, so that a false magic hit in corrupted input rarely makes resync read and
+    /// push back the up to [`MAX_RECORD_SIZE`] bytes it claims
+    fn plausible(raw: &[u8]) -> bool {
+        let (Some(version), Ok(size), Ok(desc_len)) = (
+            BinVersion::of(raw),
+            self::u32(raw, 4, 8),
+            self::u32(raw, 50, 54),
+        ) else {
+            return false;
+        };
+        let room = size.saturating_sub(MIN_RECORD_SIZE + version.checksum_len() as u32);
+
+        desc_len <= room
+            && self::u8(raw, 16).is_ok_and(|byte| TransactionType::from_byte(byte).is_ok())
+            && self::u8(raw, 49).is_ok_and(|byte| TransactionStatus::from_byte(byte).is_ok())
+    }
+
+    /// Decode a whole record, header and checksum included
+    fn decode(raw: &[u8]) -> Result<Record, ParseError> {
+        let version = BinVersion::of(raw).ok_or(ParseError::InvalidMagic)?;
+        let end = raw.len().saturating_sub(version.checksum_len());
+        let body = raw.get(8..end).ok_or(ParseError::RecordTooShort)?;
+
+        let tx_id = self::u64(body, 0, 8)?;
+        if version == BinVersion::V2 {
+            let checksum = self::u32(raw, end, raw.len())?;
+            if self::crc32(&raw[..end]) != checksum {
+                return Err(ParseError::ChecksumMismatch(tx_id));
+            }
+        }
+        let tx_type = TransactionType::from_byte(self::u8(body, 8)?)?;
+        let from_user_id = self::u64(body, 9, 17)?;
+        let to_user_id = self::u64(body, 17, 25)?;
+        let amount = Amount::from_minor(self::u64(body, 25, 33)?);
+        let timestamp = Timestamp::from_millis(self::u64(body, 33, 41)?);
+        let status = TransactionStatus::from_byte(self::u8(body, 41)?)?;
+        let desc_len = self::u32(body, 42, 46)?;
+
+        if desc_len as usize > body.len() - MIN_RECORD_SIZE as usize {
+            return Err(ParseError::RecordDamaged(tx_id));
+        }
+
+        let desc_end = 46 + desc_len as usize;
+        let desc_bytes = body.get(46..desc_end).ok_or(ParseError::RecordTooShort)?;
+
+        let description =
+            String::from_utf8(desc_bytes.to_vec()).map_err(ParseError::InvalidUtf8)?;
+        let extensions = self::decode_extensions(&body[desc_end..]).map_err(|e| match e {
+            ParseError::RecordTooShort => ParseError::RecordDamaged(tx_id),
+            e => e,
+        })?;
 
-        Ok(data)
+        Ok(Record {
+            tx_id,
+            tx_type,
+            from_user_id,
+            to_user_id,
+            amount,
+            timestamp,
+            description,
+            status,
+            extensions,
+        })
+    }
+
+    /// CRC-32 with the IEEE polynomial, the one of zlib and PNG
+    pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+        const TABLE: [u32; 256] = {
+            let mut table = [0u32; 256];
+            let mut i = 0;
+            while i < 256 {
+                let mut crc = i as u32;
+                let mut bit = 0;
+                while bit < 8 {
+                    crc = if crc & 1 == 1 {
+                        0xEDB8_8320 ^ (crc >> 1)
+                    } else {
+                        crc >> 1
+                    };
+                    bit += 1;
+                }
+                table[i] = crc;
+                i += 1;
+            }
+            table
+        };
+
+        !bytes.iter().fold(!0u32, |crc, &byte| {
+            TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+        })
+    }
+
+    /// Extension fields in the optional trailer after the description, each one
+    /// as a length prefixed name followed by a length prefixed value
+    fn decode_extensions(mut trailer: &[u8]) -> Result<BTreeMap<String, String>, ParseError> {
+        let mut extensions = BTreeMap::new();
+
+        while !trailer.is_empty() {
+            let name = self::string(&mut trailer)?;
+            let value = self::string(&mut trailer)?;
+            extensions.insert(name, value);
+        }
+
+        Ok(extensions)
+    }
+
+    fn encode_extensions(extensions: &BTreeMap<String, String>) -> Vec<u8> {
+        let mut trailer = Vec::new();
+
+        for (name, value) in extensions {
+            for part in [name, value] {
+                trailer.extend_from_slice(&(part.len() as u32).to_be_bytes());
+                trailer.extend_from_slice(part.as_bytes());
+            }
+        }
+
+        trailer
     }
 
     /// Write transactions of Record entity to binary format
@@ -110,10 +686,11 @@ pub mod bin_parser {
     ///     tx_type: core::TransactionType::Deposit,
     ///     from_user_id: 0,
     ///     to_user_id: 9223372036854775807,
-    ///     amount: 100,
-    ///     timestamp: 1633036860000,
+    ///     amount: core::Amount::from(100),
+    ///     timestamp: core::Timestamp::from(1633036860000),
     ///     status: core::TransactionStatus::Failure,
     ///     description: String::from("\"Record number 1\""),
+    ///     extensions: Default::default(),
     /// }];
     ///
     /// let mut cursor = std::io::Cursor::new(Vec::new());
@@ -126,28 +703,104 @@ pub mod bin_parser {
     ///
     /// assert_eq!(binary.len(), 71);
     /// ```
-    pub fn write_to<W: std::io::Write>(
+    pub fn write_to<W: std::io::Write, I: IntoIterator<Item = Record>>(
         writer: &mut W,
-        records: Vec<Record>,
+        records: I,
     ) -> Result<(), ParseError> {
-        let mut buffer = BufWriter::new(writer);
-        let mut data: Vec<u8> = Vec::new();
+        let mut bin_writer = BinWriter::new(writer);
 
         for record in records {
+            bin_writer.write_record(&record)?;
+        }
+
+        bin_writer.finish()
+    }
+
+    /// Incremental writer of Record entities to binary format
+    ///
+    /// Extension fields are stored in a trailer after the description, records
+    /// without them have the same bytes as before extensions existed. Records are
+    /// written in the [`BinVersion`] of the options, [`BinVersion::V1`] by default,
+    /// and the file starts with a [`BinHeader`] when the options ask for one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use formats::RecordWriter;
+    ///
+    /// let record = core::Record {
+    ///     tx_id: 1000000000000000,
+    ///     tx_type: core::TransactionType::Deposit,
+    ///     from_user_id: 0,
+    ///     to_user_id: 9223372036854775807,
+    ///     amount: core::Amount::from(100),
+    ///     timestamp: core::Timestamp::from(1633036860000),
+    ///     status: core::TransactionStatus::Failure,
+    ///     description: String::from("\"Record number 1\""),
+    ///     extensions: Default::default(),
+    /// };
+    ///
+    /// let mut binary = Vec::new();
+    /// let mut writer = formats::bin_format::bin_parser::BinWriter::new(&mut binary);
+    ///
+    /// writer.write_record(&record).unwrap();
+    /// writer.write_record(&record).unwrap();
+    /// writer.finish().unwrap();
+    /// drop(writer);
+    ///
+    /// assert_eq!(binary.len(), 142);
+    /// ```
+    pub struct BinWriter<W: std::io::Write> {
+        buffer: BufWriter<W>,
+        data: Vec<u8>,
+        version: BinVersion,
+        /// Header still to be written before the first record
+        header: Option<BinHeader>,
+    }
+
+    impl<W: std::io::Write> BinWriter<W> {
+        pub fn new(writer: W) -> Self {
+            BinWriter::with_options(writer, &BinOptions::default())
+        }
+
+        pub fn with_options(writer: W, options: &BinOptions) -> Self {
+            BinWriter {
+                buffer: BufWriter::new(writer),
+                data: Vec::new(),
+                version: options.version,
+                header: options.header.then(|| BinHeader::new(options.version)),
+            }
+        }
+    }
+
+    impl<W: std::io::Write> RecordWriter for BinWriter<W> {
+        fn write_record(&mut self, record: &Record) -> Result<(), ParseError> {
             let tx_id_bytes = record.tx_id.to_be_bytes();
             let from_user_id_bytes = record.from_user_id.to_be_bytes();
             let to_user_id_bytes = record.to_user_id.to_be_bytes();
-            let amount_bytes = record.amount.to_be_bytes();
-            let timestamp_bytes = record.timestamp.to_be_bytes();
+            let amount_bytes = record.amount.minor().to_be_bytes();
+            let timestamp_bytes = record.timestamp.millis().to_be_bytes();
             let description_bytes = record.description.as_bytes();
             let desc_len_bytes = (description_bytes.len() as u32).to_be_bytes();
-            let record_size = ((46 + description_bytes.len()) as u32).to_be_bytes();
+            let trailer = self::encode_extensions(&record.extensions);
+            let size = MIN_RECORD_SIZE as usize
+                + description_bytes.len()
+                + trailer.len()
+                + self.version.checksum_len();
+            if size > MAX_RECORD_SIZE as usize {
+                return Err(ParseError::UnexpectedRecordSize(
+                    size.try_into().unwrap_or(u32::MAX),
+                ));
+            }
+            let record_size = (size as u32).to_be_bytes();
 
             let tx_type_bytes = TransactionType::to_byte(&record.tx_type);
 
             let status_bytes = TransactionStatus::to_byte(&record.status);
 
-            data.extend_from_slice(&MAGIC);
+            let data = &mut self.data;
+            data.clear();
+            data.extend_from_slice(&self.version.magic());
             data.extend_from_slice(&record_size);
             data.extend_from_slice(&tx_id_bytes);
             data.extend_from_slice(&[tx_type_bytes]);
@@ -158,11 +811,27 @@ pub mod bin_parser {
             data.extend_from_slice(&[status_bytes]);
             data.extend_from_slice(&desc_len_bytes);
             data.extend_from_slice(description_bytes);
-        }
+            data.extend_from_slice(&trailer);
+            if self.version == BinVersion::V2 {
+                let checksum = self::crc32(data);
+                data.extend_from_slice(&checksum.to_be_bytes());
+            }
 
-        let _ = buffer.write_all(&data);
+            if let Some(header) = self.header.take() {
+                self.buffer.write_all(&header.encode())?;
+            }
+            self.buffer.write_all(data)?;
+
+            Ok(())
+        }
 
-        Ok(())
+        fn finish(&mut self) -> Result<(), ParseError> {
+            if let Some(header) = self.header.take() {
+                self.buffer.write_all(&header.encode())?;
+            }
+            self.buffer.flush()?;
+            Ok(())
+        }
     }
 
     fn u64(body: &[u8], start: usize, end: usize) -> Result<u64, ParseError> {
@@ -181,6 +850,15 @@ pub mod bin_parser {
         Ok(result)
     }
 
+    /// Take a string prefixed by its length from the start of `bytes`
+    fn string(bytes: &mut &[u8]) -> Result<String, ParseError> {
+        let len = self::u32(bytes, 0, 4)? as usize;
+        let value = bytes.get(4..4 + len).ok_or(ParseError::RecordTooShort)?;
+        *bytes = &bytes[4 + len..];
+
+        Ok(String::from_utf8(value.to_vec())?)
+    }
+
     fn u8(body: &[u8], pos: usize) -> Result<u8, ParseError> {
         let byte = *body.get(pos).ok_or(ParseError::RecordTooShort)?;
         Ok(byte)
@@ -189,7 +867,10 @@ pub mod bin_parser {
 
 #[cfg(test)]
 mod tests {
-    use core::{Record, TransactionStatus, TransactionType};
+    use core::{
+        ErrorStrategy, ParseError, ParseOptions, Position, Record, RecordSource, RecordWriter,
+        TransactionStatus, TransactionType,
+    };
     use std::io::Cursor;
 
     use super::*;
@@ -214,30 +895,33 @@ mod tests {
                 tx_type: TransactionType::Deposit,
                 from_user_id: 0,
                 to_user_id: 9223372036854775807,
-                amount: 100,
-                timestamp: 1633036860000,
+                amount: 100.into(),
+                timestamp: 1633036860000.into(),
                 status: TransactionStatus::Failure,
                 description: "\"Record number 1\"".to_string(),
+                extensions: Default::default(),
             },
             Record {
                 tx_id: 1000000000000001,
                 tx_type: TransactionType::Transfer,
                 from_user_id: 9223372036854775807,
                 to_user_id: 9223372036854775807,
-                amount: 200,
-                timestamp: 1633036920000,
+                amount: 200.into(),
+                timestamp: 1633036920000.into(),
                 status: TransactionStatus::Pending,
                 description: "\"Record number 2\"".to_string(),
+                extensions: Default::default(),
             },
             Record {
                 tx_id: 1000000000000002,
                 tx_type: TransactionType::Withdrawal,
                 from_user_id: 599094029349995112,
                 to_user_id: 0,
-                amount: 300,
-                timestamp: 1633036980000,
+                amount: 300.into(),
+                timestamp: 1633036980000.into(),
                 status: TransactionStatus::Success,
                 description: "\"Record number 3\"".to_string(),
+                extensions: Default::default(),
             },
         ]
     }
@@ -248,11 +932,98 @@ mod tests {
         let r = bin_parser::read_from(cursor).unwrap();
 
         assert_eq!(r.len(), 3);
-        assert_eq!(r[0].timestamp, 1633036860000);
+        assert_eq!(r[0].timestamp.millis(), 1633036860000);
         assert_eq!(r[1].tx_id, 1000000000000001);
         assert_eq!(r[2].from_user_id, 599094029349995112);
     }
 
+    #[test]
+    fn test_records_stop_after_error() {
+        let mut bytes = BYTES_MOCK.to_vec();
+        bytes[71] = b'X';
+
+        let mut it = bin_parser::records(Cursor::new(bytes));
+
+        assert_eq!(it.next().unwrap().unwrap().tx_id, 1000000000000000);
+        let error = it.next().unwrap().unwrap_err();
+        assert!(matches!(error.kind(), ParseError::InvalidMagic));
+        assert_eq!(
+            error.position(),
+            Some(Position::Byte {
+                offset: 71,
+                record: 1
+            })
+        );
+        assert!(it.next().is_none());
+    }
+
+    #[test]
+    fn test_records_go_on_after_damaged_record() {
+        let mut bytes = BYTES_MOCK.to_vec();
+        bytes[71 + 16] = 9;
+
+        let options = ParseOptions::new(ErrorStrategy::Collect);
+        let r = bin_parser::read_with(Cursor::new(&bytes), &options).unwrap();
+
+        assert_eq!(r.records.len(), 2);
+        assert_eq!(r.records[1].tx_id, 1000000000000002);
+        assert_eq!(r.diagnostics.len(), 1);
+        assert_eq!(r.diagnostics[0].raw, bytes[71..142].to_vec());
+    }
+
+    #[test]
+    fn test_resync_after_corruption() {
+        let mut bytes = BYTES_MOCK.to_vec();
+        bytes[72] = b'Q';
+        bytes.extend_from_slice(b"YPBN\xff\xff\xff\xffYP");
+
+        let options = bin_parser::BinOptions {
+            resync: true,
+            ..Default::default()
+        };
+        let mut it = bin_parser::records_with(Cursor::new(&bytes), &options);
+
+        assert_eq!(it.next().unwrap().unwrap().tx_id, 1000000000000000);
+        let error = it.next().unwrap().unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ParseError::CorruptedBytes {
+                start: 71,
+                end: 142
+            }
+        ));
+        assert_eq!(it.raw(), &bytes[71..142]);
+        assert_eq!(it.next().unwrap().unwrap().tx_id, 1000000000000002);
+        assert!(it.next().unwrap().is_err());
+        assert!(it.next().is_none());
+        assert_eq!(it.skipped(), [71..142, 213..223]);
+    }
+
+    #[test]
+    fn test_resync_past_false_magic() {
+        let mut bytes = BYTES_MOCK[..71].to_vec();
+        bytes.push(b'Q');
+        bytes.extend_from_slice(b"YPBN\x00\x00\xff\x00");
+        bytes.extend_from_slice(&[0xff; 46]);
+        bytes.extend_from_slice(&BYTES_MOCK[71..]);
+
+        let options = bin_parser::BinOptions {
+            resync: true,
+            ..Default::default()
+        };
+        let records: Vec<_> = bin_parser::records_with(Cursor::new(&bytes), &options).collect();
+
+        assert_eq!(records.len(), 4);
+        assert!(matches!(
+            records[1].as_ref().unwrap_err().kind(),
+            ParseError::CorruptedBytes {
+                start: 71,
+                end: 126
+            }
+        ));
+        assert_eq!(records[3].as_ref().unwrap().tx_id, 1000000000000002);
+    }
+
     #[test]
     fn test_write_data() {
         let mut cursor = Cursor::new(Vec::new());
@@ -265,4 +1036,210 @@ mod tests {
         assert!(r.is_ok());
         assert_eq!(binary.len(), BYTES_MOCK.len());
     }
+
+    #[test]
+    fn test_truncated_tail() {
+        for (len, needed, got) in [(142 + 40, 71, 40), (142 + 5, 8, 5)] {
+            let mut it = bin_parser::records(Cursor::new(&BYTES_MOCK[..len]));
+
+            assert_eq!(it.by_ref().take(2).filter(Result::is_ok).count(), 2);
+            let error = it.next().unwrap().unwrap_err();
+            assert!(
+                matches!(error.kind(), ParseError::UnexpectedEof { needed: n, got: g } if (*n, *g) == (needed, got))
+            );
+            assert_eq!(
+                error.position(),
+                Some(Position::Byte {
+                    offset: 142,
+                    record: 2
+                })
+            );
+            assert_eq!(it.raw(), &BYTES_MOCK[142..len]);
+            assert!(it.next().is_none());
+        }
+    }
+
+    /// Fails every read and write
+    struct Broken;
+
+    impl std::io::Read for Broken {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("broken"))
+        }
+    }
+
+    impl std::io::Write for Broken {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("broken"))
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_io_errors() {
+        let error = bin_parser::read_from(Broken).unwrap_err();
+        assert!(matches!(error.kind(), ParseError::Io(_)));
+
+        let error = bin_parser::write_to(&mut Broken, records_mock()).unwrap_err();
+        assert!(matches!(error, ParseError::Io(_)));
+
+        let mut record = records_mock()[0].clone();
+        record.description = "x".repeat(10_000);
+        let mut writer = bin_parser::BinWriter::new(Broken);
+        assert!(matches!(
+            writer.write_record(&record),
+            Err(ParseError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_resync_io_error() {
+        let mut bytes = BYTES_MOCK[..71].to_vec();
+        bytes.extend_from_slice(&[b'Q'; 10]);
+        let options = bin_parser::BinOptions {
+            resync: true,
+            ..Default::default()
+        };
+        let mut it = bin_parser::records_with(std::io::Read::chain(&bytes[..], Broken), &options);
+
+        assert!(it.next().unwrap().is_ok());
+        let error = it.next().unwrap().unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ParseError::CorruptedBytes { start: 71, end: 81 }
+        ));
+        assert_eq!(it.raw(), &bytes[71..]);
+        assert!(it.can_continue());
+        let error = it.next().unwrap().unwrap_err();
+        assert!(matches!(error.kind(), ParseError::Io(_)));
+        assert_eq!(error.position().unwrap().to_string(), "byte 81 (record 1)");
+        assert!(!it.can_continue());
+        assert!(it.next().is_none());
+        assert_eq!(it.skipped(), std::slice::from_ref(&(71..81)));
+    }
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(bin_parser::crc32(b"123456789"), 0xCBF4_3926);
+
+        let options = bin_parser::BinOptions {
+            version: bin_parser::BinVersion::V2,
+            ..Default::default()
+        };
+        let mut bytes = BYTES_MOCK[..71].to_vec();
+        let mut writer = bin_parser::BinWriter::with_options(&mut bytes, &options);
+        for record in &records_mock()[1..] {
+            writer.write_record(record).unwrap();
+        }
+        writer.finish().unwrap();
+        drop(writer);
+
+        assert_eq!(bytes.len(), 71 + 2 * 75);
+        assert_eq!(&bytes[71..75], b"YPB2");
+        assert_eq!(bin_parser::read_from(&bytes[..]).unwrap(), records_mock());
+
+        // a flipped bit in the amount of the second record
+        bytes[71 + 8 + 32] ^= 0x10;
+        let options = ParseOptions::new(ErrorStrategy::Collect);
+        let r = bin_parser::read_with(&bytes[..], &options).unwrap();
+        assert_eq!(r.records.len(), 2);
+        assert_eq!(
+            r.diagnostics[0].error.to_string(),
+            "byte 71 (record 1): Checksum mismatch in record with id '1000000000000001'"
+        );
+    }
+
+    #[test]
+    fn test_file_header() {
+        let options = bin_parser::BinOptions {
+            version: bin_parser::BinVersion::V2,
+            header: true,
+            ..Default::default()
+        };
+        let mut bytes = Vec::new();
+        let mut writer = bin_parser::BinWriter::with_options(&mut bytes, &options);
+        for record in &records_mock() {
+            writer.write_record(record).unwrap();
+        }
+        writer.finish().unwrap();
+        drop(writer);
+
+        assert_eq!(&bytes[..8], b"YPBF\0\x01\0\x01");
+        let mut it = bin_parser::records(&bytes[..]);
+        assert!(it.header().is_none());
+        assert_eq!(it.next().unwrap().unwrap(), records_mock()[0]);
+        let header = *it.header().unwrap();
+        assert_eq!(header.flags, bin_parser::BinHeader::CHECKSUMS);
+        assert!(header.created.millis() > 1_700_000_000_000);
+        assert_eq!(
+            it.collect::<Result<Vec<_>, _>>().unwrap(),
+            &records_mock()[1..]
+        );
+
+        let mut legacy = bin_parser::records(&BYTES_MOCK[..]);
+        assert_eq!(legacy.by_ref().count(), 3);
+        assert!(legacy.header().is_none());
+
+        let mut empty = Vec::new();
+        bin_parser::BinWriter::with_options(&mut empty, &options)
+            .finish()
+            .unwrap();
+        assert_eq!(empty.len(), 16);
+        assert!(bin_parser::read_from(&empty[..]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_file_header_errors() {
+        let mut bytes = b"YPBF\0\x02\0\0\0\0\0\0\0\0\0\0".to_vec();
+        bytes.extend_from_slice(&BYTES_MOCK);
+
+        let options = ParseOptions::new(ErrorStrategy::Skip);
+        let error = bin_parser::read_with(&bytes[..], &options).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "byte 0 (record 0): Unsupported format version 2"
+        );
+
+        let error = bin_parser::read_from(&bytes[..10]).unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ParseError::UnexpectedEof {
+                needed: 16,
+                got: 10
+            }
+        ));
+
+        let header = bin_parser::BinHeader::new(bin_parser::BinVersion::V2);
+        let mut bytes = b"YPBF\0\x01\0\x01\0\0\0\0\0\0\0\0".to_vec();
+        assert_eq!(header.record_version(), bin_parser::BinVersion::V2);
+        bytes.extend_from_slice(&BYTES_MOCK);
+        let error = bin_parser::read_from(&bytes[..]).unwrap_err();
+        assert!(matches!(error.kind(), ParseError::HeaderMismatch));
+        assert_eq!(error.position().unwrap().to_string(), "byte 16 (record 0)");
+    }
+
+    #[test]
+    fn test_extensions_trailer() {
+        let mut record = records_mock()[0].clone();
+        record.extensions = [("MERCHANT_REF", "M-42"), ("channel", "")]
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .into();
+
+        let mut binary = Vec::new();
+        bin_parser::write_to(&mut binary, [record.clone()]).unwrap();
+
+        assert_eq!(binary.len(), 71 + 4 + 12 + 4 + 4 + 4 + 7 + 4);
+        assert_eq!(bin_parser::read_from(&binary[..]).unwrap(), [record]);
+
+        binary.truncate(binary.len() - 2);
+        binary[7] -= 2;
+        let error = bin_parser::read_from(&binary[..]).unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ParseError::RecordDamaged(1000000000000000)
+        ));
+    }
 }
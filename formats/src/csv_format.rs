@@ -1,17 +1,28 @@
 pub mod csv_parser {
-    use core::{ParseError, Reader, Record, TransactionStatus, TransactionType};
+    use core::{
+        CSV_HEADER, DescriptionQuotes, ParseError, ParseOptions, ParseReport, Position, Reader,
+        Record, RecordSource, RecordWriter, TransactionStatus, TransactionType, ValueFormat,
+        Writer,
+    };
     use std::io::{self, BufRead, BufWriter, Write};
 
+    use crate::profile::Profile;
+
     pub struct Csv;
 
     impl Reader for Csv {
-        fn read_from(file: std::fs::File) -> Result<Vec<Record>, ParseError> {
-            self::read_from(file)
+        fn records<R: std::io::Read>(r: R) -> impl RecordSource {
+            self::records(r)
+        }
+    }
+
+    impl Writer for Csv {
+        fn writer<W: std::io::Write>(w: W) -> impl RecordWriter {
+            CsvWriter::new(w)
         }
     }
 
-    const HEADER: &str =
-        "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION";
+    const HEADER: &str = CSV_HEADER;
 
     /// Read transactions from csv format and converting to Record entity
     ///
@@ -26,46 +37,628 @@ pub mod csv_parser {
     /// assert_eq!(r.len(), 1);
     /// ```
     pub fn read_from<R: std::io::Read>(r: R) -> Result<Vec<Record>, ParseError> {
-        let mut reader = io::BufReader::new(r);
-        let mut data: Vec<Record> = Vec::new();
-        let mut header = String::new();
-        reader.read_line(&mut header)?;
-
-        for line in reader.lines() {
-            let line = line.map_err(|_| ParseError::MalformedLine)?;
-            let line = line.trim();
-            if line.is_empty() {
-                continue;
-            }
-            let mut it = line.split(',');
-            let tx_id = self::parse_number(it.next())?;
-
-            let tx_type_value = it.next().ok_or(ParseError::MalformedLine)?;
-            let tx_type: TransactionType = TransactionType::parse(tx_type_value)?;
-            let from_user_id = self::parse_number(it.next())?;
-            let to_user_id = self::parse_number(it.next())?;
-            let amount = self::parse_number(it.next())?;
-            let timestamp = self::parse_number(it.next())?;
-            let status_value = it.next().ok_or(ParseError::MalformedLine)?;
-            let status: TransactionStatus = TransactionStatus::parse(status_value)?;
-            let description: String = it.next().ok_or(ParseError::MalformedLine)?.to_string();
-
-            if it.next().is_some() {
-                return Err(ParseError::MalformedLine);
-            }
-
-            data.push(Record {
-                tx_id,
-                tx_type,
-                from_user_id,
-                to_user_id,
-                amount,
-                timestamp,
-                status,
-                description,
+        records(r).collect()
+    }
+
+    /// Lazily read transactions from csv format one Record at a time
+    ///
+    /// Columns are found by the names in the header line, so they can be in any order
+    /// and other columns are read as [`Record::extensions`]. Fields follow RFC 4180: they
+    /// may be quoted, a quote inside a quoted field is doubled and quoted fields may span
+    /// several lines. Lines end with LF or CRLF.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let data = b"TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n1000000000000000,DEPOSIT,0,9223372036854775807,100,1633036860000,FAILURE,\"Record number 1\"\n";
+    ///
+    /// let cursor = std::io::Cursor::new(&data[..]);
+    /// let mut it = formats::csv_format::csv_parser::records(cursor);
+    ///
+    /// assert_eq!(it.next().unwrap().unwrap().amount.minor(), 100);
+    /// assert!(it.next().is_none());
+    /// ```
+    pub fn records<R: std::io::Read>(r: R) -> CsvRecords<R> {
+        records_with(r, &CsvOptions::default())
+    }
+
+    /// Options of the csv format
+    #[derive(Debug, Clone, Default)]
+    pub struct CsvOptions {
+        pub dialect: CsvDialect,
+        pub values: ValueFormat,
+        /// Layout of the read files, written files always have the standard one
+        pub profile: Profile,
+        /// Extension columns of written files, by default those of the first record
+        pub extensions: Option<Vec<String>>,
+    }
+
+    /// Spelling of a csv file
+    ///
+    /// Both line terminators and a leading UTF-8 byte order mark are always accepted on read.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct CsvDialect {
+        pub delimiter: u8,
+        pub quote: u8,
+        /// The first line names the columns, without it columns are in the order of the csv header
+        pub header: bool,
+        /// Start written files with a UTF-8 byte order mark
+        pub bom: bool,
+        pub line_terminator: LineTerminator,
+    }
+
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub enum LineTerminator {
+        #[default]
+        Lf,
+        CrLf,
+    }
+
+    impl LineTerminator {
+        pub fn as_str(&self) -> &'static str {
+            match self {
+                LineTerminator::Lf => "\n",
+                LineTerminator::CrLf => "\r\n",
+            }
+        }
+    }
+
+    impl Default for CsvDialect {
+        fn default() -> Self {
+            CsvDialect {
+                delimiter: b',',
+                quote: b'"',
+                header: true,
+                bom: false,
+                line_terminator: LineTerminator::Lf,
+            }
+        }
+    }
+
+    impl CsvDialect {
+        /// Names accepted by [`CsvDialect::preset`]
+        pub const PRESETS: [&str; 5] = ["default", "rfc4180", "excel", "excel-semicolon", "tsv"];
+
+        /// Comma separated with CRLF line endings
+        pub fn rfc4180() -> Self {
+            CsvDialect {
+                line_terminator: LineTerminator::CrLf,
+                ..CsvDialect::default()
+            }
+        }
+
+        /// What spreadsheets write: CRLF line endings and a byte order mark
+        pub fn excel() -> Self {
+            CsvDialect {
+                bom: true,
+                ..CsvDialect::rfc4180()
+            }
+        }
+
+        /// Excel in locales with a decimal comma, e.g. exports of European banks
+        pub fn excel_semicolon() -> Self {
+            CsvDialect {
+                delimiter: b';',
+                ..CsvDialect::excel()
+            }
+        }
+
+        /// Tab separated values
+        pub fn tsv() -> Self {
+            CsvDialect {
+                delimiter: b'\t',
+                ..CsvDialect::default()
+            }
+        }
+
+        /// Dialect by one of the [`CsvDialect::PRESETS`] names
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// use formats::csv_format::csv_parser::CsvDialect;
+        ///
+        /// assert_eq!(CsvDialect::preset("TSV").unwrap().delimiter, b'\t');
+        /// assert!(CsvDialect::preset("xls").is_none());
+        /// ```
+        pub fn preset(name: &str) -> Option<CsvDialect> {
+            match name.to_ascii_lowercase().as_str() {
+                "default" => Some(CsvDialect::default()),
+                "rfc4180" => Some(CsvDialect::rfc4180()),
+                "excel" => Some(CsvDialect::excel()),
+                "excel-semicolon" => Some(CsvDialect::excel_semicolon()),
+                "tsv" => Some(CsvDialect::tsv()),
+                _ => None,
+            }
+        }
+
+        /// Check that files of the dialect can be read back: the delimiter and quote are
+        /// different ASCII characters and neither is a line break
+        ///
+        /// Readers and writers return this error before the first record.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// use formats::csv_format::csv_parser::CsvDialect;
+        ///
+        /// assert!(CsvDialect::tsv().check().is_ok());
+        /// let dialect = CsvDialect { delimiter: b'"', ..CsvDialect::default() };
+        /// assert!(dialect.check().is_err());
+        /// ```
+        pub fn check(&self) -> Result<(), ParseError> {
+            let valid = |byte: u8| byte.is_ascii() && !matches!(byte, b'\r' | b'\n');
+            if !valid(self.delimiter) || !valid(self.quote) || self.delimiter == self.quote {
+                return Err(ParseError::InvalidArgument(
+                    "csv delimiter and quote must be different ASCII characters, not line breaks",
+                ));
+            }
+            Ok(())
+        }
+    }
+
+    /// Lazily read transactions from csv format configured by `options`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use formats::csv_format::csv_parser::{self, CsvOptions};
+    ///
+    /// let data = b"TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n1000000000000000,DEPOSIT,0,9223372036854775807,12.34,1633036860000,FAILURE,\"Record number 1\"\n";
+    ///
+    /// let mut options = CsvOptions::default();
+    /// options.values.amount_scale = 2;
+    /// let mut it = csv_parser::records_with(&data[..], &options);
+    ///
+    /// assert_eq!(it.next().unwrap().unwrap().amount.minor(), 1234);
+    /// ```
+    pub fn records_with<R: std::io::Read>(r: R, options: &CsvOptions) -> CsvRecords<R> {
+        CsvRecords {
+            reader: io::BufReader::new(r),
+            options: options.clone(),
+            header_read: !options.dialect.header,
+            columns: Columns::default(),
+            raw: Vec::new(),
+            line: 0,
+            done: false,
+            fatal: false,
+        }
+    }
+
+    /// Read transactions from csv format handling broken records according to `options`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let data = b"TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n1000000000000000,DEPOSIT,0,9223372036854775807,100,1633036860000,FAILURE,\"Record number 1\"\n1000000000000001,DEPOSIT,0,1,1x0,1633036860000,FAILURE,\"Record number 2\"\n";
+    ///
+    /// let options = formats::ParseOptions::new(formats::ErrorStrategy::Collect);
+    /// let cursor = std::io::Cursor::new(&data[..]);
+    /// let r = formats::csv_format::csv_parser::read_with(cursor, &options).unwrap();
+    ///
+    /// assert_eq!(r.records.len(), 1);
+    /// assert_eq!(r.diagnostics[0].error.to_string(), "3:30: Invalid number in AMOUNT");
+    /// ```
+    pub fn read_with<R: std::io::Read>(
+        r: R,
+        options: &ParseOptions,
+    ) -> Result<ParseReport, ParseError> {
+        options.read(records(r))
+    }
+
+    /// Iterator over the records of a csv stream, see [`records`]
+    pub struct CsvRecords<R: std::io::Read> {
+        reader: io::BufReader<R>,
+        options: CsvOptions,
+        header_read: bool,
+        columns: Columns,
+        raw: Vec<u8>,
+        line: usize,
+        done: bool,
+        /// The last error ended the reading
+        fatal: bool,
+    }
+
+    impl<R: std::io::Read> Iterator for CsvRecords<R> {
+        type Item = Result<Record, ParseError>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            if self.done {
+                return None;
+            }
+
+            self.read_record().transpose()
+        }
+    }
+
+    impl<R: std::io::Read> RecordSource for CsvRecords<R> {
+        fn raw(&self) -> &[u8] {
+            &self.raw
+        }
+
+        fn can_continue(&self) -> bool {
+            !self.fatal
+        }
+    }
+
+    impl<R: std::io::Read> CsvRecords<R> {
+        fn read_record(&mut self) -> Result<Option<Record>, ParseError> {
+            if self.line == 0
+                && let Err(e) = self.options.dialect.check()
+            {
+                (self.done, self.fatal) = (true, true);
+                return Err(e);
+            }
+            if !self.header_read {
+                self.header_read = true;
+                match self.read_header() {
+                    Ok(Some(columns)) => self.columns = columns,
+                    Ok(None) => return Ok(None),
+                    Err(e) => {
+                        // records can not be read without knowing their columns
+                        (self.done, self.fatal) = (true, true);
+                        return Err(e);
+                    }
+                }
+            }
+
+            match self.read_fields()? {
+                Some(fields) => parse_record(fields, &self.columns, &self.options).map(Some),
+                None => Ok(None),
+            }
+        }
+
+        fn read_header(&mut self) -> Result<Option<Columns>, ParseError> {
+            let Some(header) = self.read_fields()? else {
+                return Ok(None);
+            };
+
+            Columns::from_header(&header, &self.options.profile)
+                .map(Some)
+                .map_err(|e| {
+                    e.at(Position::Line {
+                        line: header[0].line,
+                        column: None,
+                    })
+                })
+        }
+
+        /// Read lines up to the end of the next record, blank lines between records are skipped
+        fn read_fields(&mut self) -> Result<Option<Vec<Field>>, ParseError> {
+            let mut tokenizer = Tokenizer::new(&self.options.dialect);
+            self.raw.clear();
+
+            loop {
+                let start = self.raw.len();
+                match self.reader.read_until(b'\n', &mut self.raw) {
+                    Ok(0) => {
+                        self.done = true;
+                        if !tokenizer.finish() {
+                            return Ok(None);
+                        }
+                        break;
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        (self.done, self.fatal) = (true, true);
+                        return Err(ParseError::Io(e).at(Position::Line {
+                            line: self.line + 1,
+                            column: None,
+                        }));
+                    }
+                }
+                self.line += 1;
+
+                let mut line = &self.raw[start..];
+                if self.line == 1 {
+                    line = line.strip_prefix(BOM).unwrap_or(line);
+                }
+                // a line of spaces or delimiters is a record of empty fields
+                if tokenizer.is_empty() && matches!(line, b"" | b"\n" | b"\r\n") {
+                    self.raw.clear();
+                } else if tokenizer.feed(line, self.line) {
+                    break;
+                }
+            }
+
+            tokenizer.take_record().map(Some)
+        }
+    }
+
+    const BOM: &[u8] = "\u{feff}".as_bytes();
+
+    /// Splits RFC 4180 records into fields, fed one line at a time since
+    /// quoted fields may contain line breaks
+    ///
+    /// After a syntax error the rest of the record is still consumed, so
+    /// reading can go on with the next one.
+    #[derive(Default)]
+    struct Tokenizer {
+        delimiter: u8,
+        quote: u8,
+        fields: Vec<Field>,
+        value: Vec<u8>,
+        /// Line and column of the current field
+        start: Option<(usize, usize)>,
+        /// Line and column of the next byte
+        next: (usize, usize),
+        in_quotes: bool,
+        closed: bool,
+        error: Option<ParseError>,
+    }
+
+    impl Tokenizer {
+        fn new(dialect: &CsvDialect) -> Self {
+            Tokenizer {
+                delimiter: dialect.delimiter,
+                quote: dialect.quote,
+                ..Tokenizer::default()
+            }
+        }
+
+        fn is_empty(&self) -> bool {
+            self.fields.is_empty() && self.start.is_none()
+        }
+
+        /// Feed the next line, returns `true` when it ends the record
+        fn feed(&mut self, line: &[u8], number: usize) -> bool {
+            let mut bytes = line.iter().copied().enumerate().peekable();
+
+            while let Some((i, byte)) = bytes.next() {
+                let position = (number, i + 1);
+                let next = bytes.peek().map(|(_, b)| *b);
+                self.next = (number, i + 2);
+                let start = *self.start.get_or_insert(position);
+
+                if self.in_quotes {
+                    if byte != self.quote {
+                        self.value.push(byte);
+                    } else if next == Some(self.quote) {
+                        self.value.push(self.quote);
+                        bytes.next();
+                    } else {
+                        self.in_quotes = false;
+                        self.closed = true;
+                    }
+                } else if byte == self.delimiter {
+                    self.end_field();
+                } else if byte == b'\n' || (byte == b'\r' && matches!(next, Some(b'\n') | None)) {
+                    self.end_field();
+                    return true;
+                } else if byte == self.quote && start == position {
+                    self.in_quotes = true;
+                } else {
+                    if self.closed {
+                        // only a delimiter or the end of the record may follow a closing quote
+                        self.fail(ParseError::MalformedLine, position);
+                    }
+                    self.value.push(byte);
+                }
+            }
+
+            false
+        }
+
+        /// End of input, returns `true` when there is a record left
+        fn finish(&mut self) -> bool {
+            if self.is_empty() {
+                return false;
+            }
+            if self.in_quotes {
+                let start = self.start.unwrap_or(self.next);
+                self.fail(ParseError::MalformedLine, start);
+            }
+            self.end_field();
+            true
+        }
+
+        fn end_field(&mut self) {
+            let (line, column) = self.start.take().unwrap_or(self.next);
+            let value = String::from_utf8(std::mem::take(&mut self.value)).unwrap_or_else(|e| {
+                let value = String::from_utf8_lossy(e.as_bytes()).into_owned();
+                self.fail(ParseError::InvalidUtf8(e), (line, column));
+                value
             });
+
+            self.fields.push(Field {
+                line,
+                column,
+                value,
+                quoted: self.closed,
+            });
+            self.closed = false;
+        }
+
+        fn fail(&mut self, error: ParseError, (line, column): (usize, usize)) {
+            if self.error.is_none() {
+                self.error = Some(error.at(Position::Line {
+                    line,
+                    column: Some(column),
+                }));
+            }
+        }
+
+        fn take_record(&mut self) -> Result<Vec<Field>, ParseError> {
+            match self.error.take() {
+                Some(error) => Err(error),
+                None => Ok(std::mem::take(&mut self.fields)),
+            }
+        }
+    }
+
+    /// Names of the columns every csv input has, in the order they are written
+    const COLUMNS: [&str; 8] = [
+        "TX_ID",
+        "TX_TYPE",
+        "FROM_USER_ID",
+        "TO_USER_ID",
+        "AMOUNT",
+        "TIMESTAMP",
+        "STATUS",
+        "DESCRIPTION",
+    ];
+
+    /// Where each of [`COLUMNS`] is in the records of an input, `None` for
+    /// columns the profile gives a default for
+    struct Columns {
+        indexes: [Option<usize>; 8],
+        /// Index and name of the columns read as extension fields
+        extensions: Vec<(usize, String)>,
+        /// Number of fields of every record
+        width: usize,
+    }
+
+    impl Default for Columns {
+        fn default() -> Self {
+            Columns {
+                indexes: [0, 1, 2, 3, 4, 5, 6, 7].map(Some),
+                extensions: Vec::new(),
+                width: COLUMNS.len(),
+            }
+        }
+    }
+
+    impl Columns {
+        /// Map columns by their names in any order and case, other columns are extension
+        /// fields and of a repeated name the first column is used
+        ///
+        /// The profile renames columns and makes them optional by giving a default,
+        /// without a type the type of signed amounts comes from their sign.
+        fn from_header(header: &[Field], profile: &Profile) -> Result<Columns, ParseError> {
+            let mut indexes = [None; 8];
+            let names = profile.columns.get();
+            let defaults = profile.defaults.get();
+
+            for (i, field) in COLUMNS.into_iter().enumerate() {
+                let name = names[i].unwrap_or(field);
+                indexes[i] = header
+                    .iter()
+                    .position(|f| f.value.trim().eq_ignore_ascii_case(name));
+
+                let optional = defaults[i].is_some() || (i == 1 && profile.amount.signed);
+                if indexes[i].is_none() && !optional {
+                    return Err(ParseError::MissingColumn(field));
+                }
+            }
+
+            let mut extensions: Vec<(usize, String)> = Vec::new();
+            for (index, field) in header.iter().enumerate() {
+                let name = field.value.trim();
+                if !indexes.contains(&Some(index))
+                    && Record::is_extension_name(name)
+                    && !extensions.iter().any(|(_, known)| known == name)
+                {
+                    extensions.push((index, name.to_string()));
+                }
+            }
+
+            Ok(Columns {
+                indexes,
+                extensions,
+                width: header.len(),
+            })
+        }
+    }
+
+    fn parse_record(
+        fields: Vec<Field>,
+        columns: &Columns,
+        options: &CsvOptions,
+    ) -> Result<Record, ParseError> {
+        let line = fields[0].line;
+        if fields.len() != columns.width {
+            return Err(ParseError::MalformedLine.at(Position::Line {
+                line,
+                column: fields.get(columns.width).map(|f| f.column),
+            }));
+        }
+
+        let profile = &options.profile;
+        let defaults = profile.defaults.get();
+        let value = |i: usize| match columns.indexes[i] {
+            Some(index) => Value {
+                text: &fields[index].value,
+                position: Position::Line {
+                    line: fields[index].line,
+                    column: Some(fields[index].column),
+                },
+            },
+            // defaults are positioned at the record
+            None => Value {
+                text: defaults[i].unwrap_or_default(),
+                position: Position::Line { line, column: None },
+            },
+        };
+
+        let tx_id = value(0).parse(|v| parse_number(v, "TX_ID"))?;
+        let (amount, negative) =
+            value(4).parse(|v| profile.amount(v, options.values.amount_scale))?;
+        let tx_type = match (columns.indexes[1], defaults[1]) {
+            (None, None) if negative => TransactionType::Withdrawal,
+            (None, None) => TransactionType::Deposit,
+            _ => value(1).parse(|v| profile.tx_type(v))?,
+        };
+        if negative && tx_type != TransactionType::Withdrawal {
+            // only the transaction type can keep the sign
+            return Err(ParseError::InvalidNumber("AMOUNT").at(value(4).position));
+        }
+
+        Ok(Record {
+            tx_id,
+            tx_type,
+            from_user_id: value(2).parse(|v| parse_number(v, "FROM_USER_ID"))?,
+            to_user_id: value(3).parse(|v| parse_number(v, "TO_USER_ID"))?,
+            amount,
+            timestamp: value(5).parse(|v| profile.timestamp(v))?,
+            status: value(6).parse(|v| profile.status(v))?,
+            description: match (options.values.description, columns.indexes[7]) {
+                (DescriptionQuotes::Legacy, Some(index)) => {
+                    fields[index].raw(options.dialect.quote)
+                }
+                _ => value(7).text.to_string(),
+            },
+            // empty fields are records without the extension
+            extensions: columns
+                .extensions
+                .iter()
+                .filter(|(index, _)| !fields[*index].value.is_empty())
+                .map(|(index, name)| (name.clone(), fields[*index].value.clone()))
+                .collect(),
+        })
+    }
+
+    struct Field {
+        line: usize,
+        column: usize,
+        value: String,
+        quoted: bool,
+    }
+
+    impl Field {
+        /// The field as written in the input, quotes included
+        fn raw(&self, quote: u8) -> String {
+            if !self.quoted {
+                return self.value.clone();
+            }
+            let quote = quote as char;
+            let escaped = self.value.replace(quote, &format!("{0}{0}", quote));
+            format!("{0}{1}{0}", quote, escaped)
+        }
+    }
+
+    /// Text of a record field and where it is in the input
+    struct Value<'a> {
+        text: &'a str,
+        position: Position,
+    }
+
+    impl Value<'_> {
+        fn parse<T, E: Into<ParseError>>(
+            &self,
+            parse: impl FnOnce(&str) -> Result<T, E>,
+        ) -> Result<T, ParseError> {
+            parse(self.text).map_err(|e| e.into().at(self.position))
         }
-        Ok(data)
     }
 
     /// Write transactions of Record entity to csv format
@@ -81,10 +674,11 @@ pub mod csv_parser {
     ///     tx_type: core::TransactionType::Deposit,
     ///     from_user_id: 0,
     ///     to_user_id: 9223372036854775807,
-    ///     amount: 100,
-    ///     timestamp: 1633036860000,
+    ///     amount: core::Amount::from(100),
+    ///     timestamp: core::Timestamp::from(1633036860000),
     ///     status: core::TransactionStatus::Failure,
     ///     description: String::from("\"Record number 1\""),
+    ///     extensions: Default::default(),
     /// }];
     ///
     /// let mut cursor = std::io::Cursor::new(Vec::new());
@@ -97,46 +691,241 @@ pub mod csv_parser {
     ///
     /// assert_eq!(lines.len(), 2);
     /// ```
-    pub fn write_to<W: std::io::Write>(
+    pub fn write_to<W: std::io::Write, I: IntoIterator<Item = Record>>(
         writer: &mut W,
-        records: Vec<Record>,
+        records: I,
     ) -> Result<(), ParseError> {
-        let mut buffer = BufWriter::new(writer);
-        let mut data = format!("{}\n", HEADER);
+        let mut csv_writer = CsvWriter::new(writer);
 
         for record in records {
-            data.push_str(&format!(
-                "{},{},{},{},{},{},{},{}\n",
-                record.tx_id,
-                TransactionType::to_str(&record.tx_type),
-                record.from_user_id,
-                record.to_user_id,
-                record.amount,
-                record.timestamp,
-                TransactionStatus::to_str(&record.status),
-                record.description
-            ));
+            csv_writer.write_record(&record)?;
         }
 
-        write!(buffer, "{}", data)?;
+        csv_writer.finish()
+    }
 
-        Ok(())
+    /// Incremental writer of Record entities to csv format
+    ///
+    /// The header line is written before the first record, or by
+    /// [`RecordWriter::finish`] when no record was written at all.
+    ///
+    /// Fields are quoted when needed, so every written record is read back equal
+    /// by [`records_with`] with the same options. Only descriptions with
+    /// [`DescriptionQuotes::Legacy`] are written as they are, quotes included.
+    ///
+    /// The extension columns are those [`CsvOptions::extensions`] declares, or else
+    /// the extension fields of the first record. Records leave the columns of
+    /// extensions they lack empty and can not add other ones, which is a
+    /// [`ParseError::InvalidExtension`]. Without a header no extension fields can be
+    /// written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use formats::RecordWriter;
+    ///
+    /// let record = core::Record {
+    ///     tx_id: 1000000000000000,
+    ///     tx_type: core::TransactionType::Deposit,
+    ///     from_user_id: 0,
+    ///     to_user_id: 9223372036854775807,
+    ///     amount: core::Amount::from(100),
+    ///     timestamp: core::Timestamp::from(1633036860000),
+    ///     status: core::TransactionStatus::Failure,
+    ///     description: String::from("\"Record number 1\""),
+    ///     extensions: Default::default(),
+    /// };
+    ///
+    /// let mut output = Vec::new();
+    /// let mut writer = formats::csv_format::csv_parser::CsvWriter::new(&mut output);
+    ///
+    /// writer.write_record(&record).unwrap();
+    /// writer.write_record(&record).unwrap();
+    /// writer.finish().unwrap();
+    /// drop(writer);
+    ///
+    /// assert_eq!(String::from_utf8(output).unwrap().lines().count(), 3);
+    /// ```
+    pub struct CsvWriter<W: std::io::Write> {
+        buffer: BufWriter<W>,
+        options: CsvOptions,
+        started: bool,
+        /// Names of the extension columns
+        extensions: Vec<String>,
     }
 
-    fn parse_number(value: Option<&str>) -> Result<u64, ParseError> {
-        let str = value.ok_or(ParseError::MalformedLine)?;
-        let result = str.parse::<u64>().map_err(|_| ParseError::InvalidNumber)?;
-        Ok(result)
+    impl<W: std::io::Write> CsvWriter<W> {
+        pub fn new(writer: W) -> Self {
+            CsvWriter::with_options(writer, &CsvOptions::default())
+        }
+
+        pub fn with_options(writer: W, options: &CsvOptions) -> Self {
+            CsvWriter {
+                buffer: BufWriter::new(writer),
+                options: options.clone(),
+                started: false,
+                extensions: Vec::new(),
+            }
+        }
+
+        /// Write the byte order mark and header the dialect asks for, the extension
+        /// columns are the declared ones or those of the `first` record
+        fn start(&mut self, first: Option<&Record>) -> Result<(), ParseError> {
+            if self.started {
+                return Ok(());
+            }
+
+            let dialect = &self.options.dialect;
+            dialect.check()?;
+            if dialect.header {
+                self.extensions = match (&self.options.extensions, first) {
+                    (Some(names), _) => names.clone(),
+                    (None, Some(record)) => record.extensions.keys().cloned().collect(),
+                    (None, None) => Vec::new(),
+                };
+                if let Some(name) = self
+                    .extensions
+                    .iter()
+                    .find(|name| !Record::is_extension_name(name))
+                {
+                    return Err(ParseError::InvalidExtension(name.clone()));
+                }
+            }
+            self.started = true;
+
+            if dialect.bom {
+                self.buffer.write_all(BOM)?;
+            }
+            if dialect.header {
+                let delimiter = dialect.delimiter as char;
+                let mut header = HEADER.replace(',', &delimiter.to_string());
+                for name in &self.extensions {
+                    header.push_str(&format!("{}{}", delimiter, Quoted(name, dialect)));
+                }
+                write!(
+                    self.buffer,
+                    "{}{}",
+                    header,
+                    dialect.line_terminator.as_str()
+                )?;
+            }
+            Ok(())
+        }
+    }
+
+    impl<W: std::io::Write> RecordWriter for CsvWriter<W> {
+        fn write_record(&mut self, record: &Record) -> Result<(), ParseError> {
+            if let Some(name) = record.invalid_extension() {
+                return Err(ParseError::InvalidExtension(name.to_string()));
+            }
+            self.start(Some(record))?;
+            self.write_row(record)
+        }
+
+        fn finish(&mut self) -> Result<(), ParseError> {
+            self.start(None)?;
+            self.buffer.flush()?;
+            Ok(())
+        }
+    }
+
+    impl<W: std::io::Write> CsvWriter<W> {
+        fn write_row(&mut self, record: &Record) -> Result<(), ParseError> {
+            if let Some(name) = record
+                .extensions
+                .keys()
+                .find(|name| !self.extensions.contains(name))
+            {
+                return Err(ParseError::InvalidExtension(name.clone()));
+            }
+
+            let values = &self.options.values;
+            let fields = [
+                record.tx_id.to_string(),
+                TransactionType::to_str(&record.tx_type).to_string(),
+                record.from_user_id.to_string(),
+                record.to_user_id.to_string(),
+                record.amount.format(values.amount_scale),
+                values.timestamp.format(record.timestamp),
+                TransactionStatus::to_str(&record.status).to_string(),
+            ];
+
+            let extensions = self
+                .extensions
+                .iter()
+                .map(|name| record.extensions.get(name).map_or("", String::as_str));
+
+            let dialect = &self.options.dialect;
+            for field in fields.iter().map(String::as_str) {
+                write!(self.buffer, "{}", Quoted(field, dialect))?;
+                write!(self.buffer, "{}", dialect.delimiter as char)?;
+            }
+            match values.description {
+                DescriptionQuotes::Syntax => {
+                    write!(self.buffer, "{}", Quoted(&record.description, dialect))?
+                }
+                DescriptionQuotes::Legacy => write!(self.buffer, "{}", record.description)?,
+            }
+            for value in extensions {
+                write!(
+                    self.buffer,
+                    "{}{}",
+                    dialect.delimiter as char,
+                    Quoted(value, dialect)
+                )?;
+            }
+            write!(self.buffer, "{}", dialect.line_terminator.as_str())?;
+
+            Ok(())
+        }
+    }
+
+    /// Field written as is, or quoted when it holds a delimiter, quote or line break
+    struct Quoted<'a>(&'a str, &'a CsvDialect);
+
+    impl std::fmt::Display for Quoted<'_> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            let (delimiter, quote) = (self.1.delimiter as char, self.1.quote as char);
+            if self.0.contains([delimiter, quote, '\r', '\n']) {
+                let escaped = self.0.replace(quote, &format!("{0}{0}", quote));
+                write!(f, "{0}{1}{0}", quote, escaped)
+            } else {
+                f.write_str(self.0)
+            }
+        }
+    }
+
+    fn parse_number(value: &str, field: &'static str) -> Result<u64, ParseError> {
+        value
+            .parse::<u64>()
+            .map_err(|_| ParseError::InvalidNumber(field))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use core::{Record, TransactionStatus, TransactionType};
+    use core::{
+        CSV_HEADER, ParseError, Position, Record, RecordWriter, TransactionStatus, TransactionType,
+    };
     use std::io::{BufRead, BufReader, Cursor};
 
     use super::*;
 
+    /// Record the single record tests build their variants from
+    fn record() -> Record {
+        Record {
+            tx_id: 1,
+            tx_type: TransactionType::Deposit,
+            from_user_id: 0,
+            to_user_id: 2,
+            amount: 100.into(),
+            timestamp: 1633036860000.into(),
+            status: TransactionStatus::Success,
+            description: "Record number 1".to_string(),
+            extensions: Default::default(),
+        }
+    }
+
     #[test]
     fn test_load_data() {
         let data = b"TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n1000000000000000,DEPOSIT,0,9223372036854775807,100,1633036860000,FAILURE,\"Record number 1\"\n1000000000000001,TRANSFER,9223372036854775807,9223372036854775807,200,1633036920000,PENDING,\"Record number 2\"\n1000000000000002,WITHDRAWAL,599094029349995112,0,300,1633036980000,SUCCESS,\"Record number 3\"";
@@ -155,30 +944,33 @@ mod tests {
                 tx_type: TransactionType::Deposit,
                 from_user_id: 0,
                 to_user_id: 9223372036854775807,
-                amount: 100,
-                timestamp: 1633036860000,
+                amount: 100.into(),
+                timestamp: 1633036860000.into(),
                 status: TransactionStatus::Failure,
                 description: "Record number 1".to_string(),
+                extensions: Default::default(),
             },
             Record {
                 tx_id: 1000000000000001,
                 tx_type: TransactionType::Transfer,
                 from_user_id: 9223372036854775807,
                 to_user_id: 9223372036854775807,
-                amount: 200,
-                timestamp: 1633036920000,
+                amount: 200.into(),
+                timestamp: 1633036920000.into(),
                 status: TransactionStatus::Pending,
                 description: "Record number 2".to_string(),
+                extensions: Default::default(),
             },
             Record {
                 tx_id: 1000000000000002,
                 tx_type: TransactionType::Withdrawal,
                 from_user_id: 599094029349995112,
                 to_user_id: 0,
-                amount: 300,
-                timestamp: 1633036980000,
+                amount: 300.into(),
+                timestamp: 1633036980000.into(),
                 status: TransactionStatus::Success,
                 description: "Record number 3".to_string(),
+                extensions: Default::default(),
             },
         ];
 
@@ -195,4 +987,436 @@ mod tests {
 
         assert_eq!(lines.len(), 4);
     }
+
+    #[test]
+    fn test_writer_header_once() {
+        let record = record();
+
+        let mut empty = Vec::new();
+        csv_parser::CsvWriter::new(&mut empty).finish().unwrap();
+        assert_eq!(String::from_utf8(empty).unwrap().lines().count(), 1);
+
+        let mut output = Vec::new();
+        let mut writer = csv_parser::CsvWriter::new(&mut output);
+        writer.write_record(&record).unwrap();
+        writer.write_record(&record).unwrap();
+        writer.finish().unwrap();
+        drop(writer);
+
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches("TX_ID").count(), 1);
+        assert_eq!(csv_parser::read_from(output.as_bytes()).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_error_position() {
+        let data = b"TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n1000000000000000,DEPOSIT,0,9223372036854775807,100,1633036860000,FAILURE,\"Record number 1\"\n\n1000000000000001,TRANSFER,1,2,2x0,1633036920000,PENDING,\"Record number 2\"\n";
+
+        let error = csv_parser::read_from(&data[..]).unwrap_err();
+
+        assert!(matches!(error.kind(), ParseError::InvalidNumber("AMOUNT")));
+        assert_eq!(
+            error.position(),
+            Some(Position::Line {
+                line: 4,
+                column: Some(31)
+            })
+        );
+        assert_eq!(error.to_string(), "4:31: Invalid number in AMOUNT");
+    }
+
+    #[test]
+    fn test_amount_scale() {
+        let record = Record {
+            amount: 1205.into(),
+            ..record()
+        };
+        let mut options = csv_parser::CsvOptions::default();
+        options.values.amount_scale = 2;
+
+        let mut output = Vec::new();
+        let mut writer = csv_parser::CsvWriter::with_options(&mut output, &options);
+        writer.write_record(&record).unwrap();
+        writer.finish().unwrap();
+        drop(writer);
+
+        assert!(String::from_utf8_lossy(&output).contains(",0,2,12.05,"));
+        let r = csv_parser::records_with(&output[..], &options).collect::<Result<Vec<_>, _>>();
+        assert_eq!(r.unwrap(), [record]);
+
+        let error = csv_parser::read_from(&output[..]).unwrap_err();
+        assert!(matches!(error.kind(), ParseError::InvalidNumber("AMOUNT")));
+    }
+
+    #[test]
+    fn test_rfc4180_fields() {
+        let data = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\r\n\
+            1,DEPOSIT,0,2,100,1633036860000,SUCCESS,\"Paid, with \"\"care\"\"\"\r\n\
+            \"2\",DEPOSIT,0,2,100,1633036860000,SUCCESS,\"two\r\nlines\"\r\n\
+            3,DEPOSIT,0,2,100,1633036860000,SUCCESS,\n\
+            4,DEPOSIT,0,2,100,1633036860000,SUCCESS,say \"hi\"";
+
+        let r = csv_parser::read_from(data.as_bytes()).unwrap();
+        let descriptions: Vec<&str> = r.iter().map(|r| r.description.as_str()).collect();
+
+        assert_eq!(r[1].tx_id, 2);
+        assert_eq!(
+            descriptions,
+            ["Paid, with \"care\"", "two\r\nlines", "", "say \"hi\""]
+        );
+    }
+
+    #[test]
+    fn test_rfc4180_errors() {
+        let data = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n\
+            1,DEPOSIT,0,2,100,1633036860000,SUCCESS,\"multi\nline\"x\n\
+            2,DEPOSIT,0,2,1x0,1633036860000,SUCCESS,ok\n\
+            3,DEPOSIT,0,2,100,1633036860000,SUCCESS,\"never closed\n";
+
+        let errors: Vec<String> = csv_parser::records(data.as_bytes())
+            .map(|r| r.unwrap_err().to_string())
+            .collect();
+
+        assert_eq!(
+            errors,
+            [
+                "3:6: Malformed line",
+                "4:15: Invalid number in AMOUNT",
+                "5:41: Malformed line"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let descriptions = [
+            "",
+            " ",
+            "Record number 1",
+            "\"Record number 1\"",
+            "a,b",
+            "\"",
+            "two\nlines",
+            "crlf\r\n",
+            "\r",
+            ",\"\r\n\"\",",
+            "  padded  ",
+            "ünïcödé 💸",
+        ];
+        let records: Vec<Record> = descriptions
+            .iter()
+            .enumerate()
+            .map(|(i, description)| Record {
+                tx_id: i as u64,
+                tx_type: TransactionType::Withdrawal,
+                from_user_id: u64::MAX,
+                to_user_id: 0,
+                amount: (u64::MAX - i as u64).into(),
+                timestamp: match i % 2 {
+                    0 => 1633036860001 + i as u64,
+                    _ => u64::MAX - i as u64,
+                }
+                .into(),
+                status: TransactionStatus::Pending,
+                description: description.to_string(),
+                extensions: Default::default(),
+            })
+            .collect();
+
+        let mut scaled = csv_parser::CsvOptions::default();
+        scaled.values.amount_scale = 2;
+        scaled.values.timestamp = core::TimestampFormat::Rfc3339 {
+            offset_minutes: -600,
+        };
+
+        for options in [csv_parser::CsvOptions::default(), scaled] {
+            let mut output = Vec::new();
+            let mut writer = csv_parser::CsvWriter::with_options(&mut output, &options);
+            for record in &records {
+                writer.write_record(record).unwrap();
+            }
+            writer.finish().unwrap();
+            drop(writer);
+
+            let r = csv_parser::records_with(&output[..], &options).collect::<Result<Vec<_>, _>>();
+            assert_eq!(r.unwrap(), records);
+        }
+    }
+
+    #[test]
+    fn test_header_columns() {
+        let data = "description,Merchant,TX_TYPE,TX_ID,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS\n\
+            \"Record number 1\",shop,DEPOSIT,1,0,2,100,1633036860000,SUCCESS\n";
+
+        let r = csv_parser::read_from(data.as_bytes()).unwrap();
+
+        assert_eq!(r[0].tx_id, 1);
+        assert_eq!(r[0].to_user_id, 2);
+        assert_eq!(r[0].description, "Record number 1");
+
+        let data = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,TIMESTAMP,STATUS,DESCRIPTION\n\
+            1,DEPOSIT,0,2,1633036860000,SUCCESS,x\n";
+        let mut it = csv_parser::records(data.as_bytes());
+
+        let error = it.next().unwrap().unwrap_err();
+        assert!(matches!(error.kind(), ParseError::MissingColumn("AMOUNT")));
+        assert_eq!(error.to_string(), "1: Missing column AMOUNT");
+        assert!(it.next().is_none());
+
+        let options = core::ParseOptions::new(core::ErrorStrategy::Skip);
+        let error = csv_parser::read_with(data.as_bytes(), &options).unwrap_err();
+        assert!(matches!(error.kind(), ParseError::MissingColumn("AMOUNT")));
+    }
+
+    #[test]
+    fn test_dialects() {
+        let record = Record {
+            amount: 1205.into(),
+            description: "Paid; thanks\tbye".to_string(),
+            ..record()
+        };
+
+        for dialect in csv_parser::CsvDialect::PRESETS {
+            let mut options = csv_parser::CsvOptions {
+                dialect: csv_parser::CsvDialect::preset(dialect).unwrap(),
+                ..Default::default()
+            };
+            options.values.amount_scale = 2;
+
+            let mut output = Vec::new();
+            let mut writer = csv_parser::CsvWriter::with_options(&mut output, &options);
+            writer.write_record(&record).unwrap();
+            writer.finish().unwrap();
+            drop(writer);
+
+            let r = csv_parser::records_with(&output[..], &options).collect::<Result<Vec<_>, _>>();
+            assert_eq!(r.unwrap(), std::slice::from_ref(&record), "{}", dialect);
+        }
+
+        let data = "\u{feff}TX_ID;TX_TYPE;FROM_USER_ID;TO_USER_ID;AMOUNT;TIMESTAMP;STATUS;DESCRIPTION\r\n\
+            1;DEPOSIT;0;2;12.05;1633036860000;SUCCESS;\"Paid; thanks\tbye\"\r\n";
+        let mut options = csv_parser::CsvOptions {
+            dialect: csv_parser::CsvDialect::excel_semicolon(),
+            ..Default::default()
+        };
+        options.values.amount_scale = 2;
+
+        let mut output = Vec::new();
+        let mut writer = csv_parser::CsvWriter::with_options(&mut output, &options);
+        writer.write_record(&record).unwrap();
+        writer.finish().unwrap();
+        drop(writer);
+
+        assert_eq!(String::from_utf8(output).unwrap(), data);
+    }
+
+    #[test]
+    fn test_no_header() {
+        let data = "1\tDEPOSIT\t0\t2\t100\t1633036860000\tSUCCESS\tx\n";
+        let options = csv_parser::CsvOptions {
+            dialect: csv_parser::CsvDialect {
+                header: false,
+                ..csv_parser::CsvDialect::tsv()
+            },
+            ..Default::default()
+        };
+
+        let r = csv_parser::records_with(data.as_bytes(), &options).collect::<Result<Vec<_>, _>>();
+
+        assert_eq!(r.unwrap()[0].description, "x");
+
+        for (delimiter, quote) in [(b'"', b'"'), (b'\n', b'"'), (b',', b'\r'), (0xe9, b'"')] {
+            let dialect = csv_parser::CsvDialect {
+                delimiter,
+                quote,
+                ..csv_parser::CsvDialect::default()
+            };
+            let options = csv_parser::CsvOptions {
+                dialect,
+                ..Default::default()
+            };
+            let mut it = csv_parser::records_with(data.as_bytes(), &options);
+            assert!(matches!(
+                it.next(),
+                Some(Err(ParseError::InvalidArgument(_)))
+            ));
+            assert!(it.next().is_none());
+
+            let mut writer = csv_parser::CsvWriter::with_options(Vec::new(), &options);
+            let error = writer.finish().unwrap_err();
+            assert!(matches!(error, ParseError::InvalidArgument(_)));
+        }
+
+        let data = format!("\n{data}\r\n\t\t\t\t\t\t\t\n{data}");
+        let r: Vec<_> = csv_parser::records_with(data.as_bytes(), &options).collect();
+        assert_eq!(r.len(), 3);
+        assert_eq!(
+            r[1].as_ref().unwrap_err().to_string(),
+            "4:1: Invalid number in TX_ID"
+        );
+    }
+
+    #[test]
+    fn test_profile() {
+        let profile = crate::Profile::from_toml(
+            r#"
+            [columns]
+            tx_id = "Reference"
+            amount = "Betrag"
+            timestamp = "Buchungstag"
+            status = "Status"
+            description = "Verwendungszweck"
+
+            [defaults]
+            from_user_id = "0"
+            to_user_id = "0"
+
+            [values.status]
+            Gebucht = "SUCCESS"
+            Vorgemerkt = "PENDING"
+
+            [amount]
+            scale = 2
+            decimal_separator = ","
+            signed = true
+
+            [timestamp]
+            format = "DD.MM.YYYY"
+            utc_offset = "+02:00"
+            "#,
+        )
+        .unwrap();
+        let data = "Buchungstag;Reference;Betrag;Status;Verwendungszweck\n\
+            01.10.2021;1;-12,50;gebucht;Miete\n\
+            02.10.2021;2;+1500;Vorgemerkt;Gehalt\n\
+            03.10.2021;3;7.5;SUCCESS;x\n";
+        let options = csv_parser::CsvOptions {
+            dialect: csv_parser::CsvDialect::excel_semicolon(),
+            profile,
+            ..Default::default()
+        };
+
+        let mut it = csv_parser::records_with(data.as_bytes(), &options);
+        let (first, second) = (it.next().unwrap().unwrap(), it.next().unwrap().unwrap());
+
+        assert_eq!(first.tx_type, TransactionType::Withdrawal);
+        assert_eq!(first.amount.minor(), 1250);
+        assert_eq!(first.timestamp.to_rfc3339(120), "2021-10-01T00:00:00+02:00");
+        assert_eq!(first.status, TransactionStatus::Success);
+        assert_eq!(first.description, "Miete");
+        assert_eq!(second.tx_type, TransactionType::Deposit);
+        assert_eq!(second.amount.minor(), 150000);
+        assert_eq!(second.status, TransactionStatus::Pending);
+        assert_eq!(
+            it.next().unwrap().unwrap_err().to_string(),
+            "4:14: Invalid number in AMOUNT"
+        );
+
+        let error = csv_parser::records_with(&b"Reference;Betrag\n"[..], &options)
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(error.to_string(), "1: Missing column TIMESTAMP");
+
+        let data = "Buchungstag;Reference;TX_TYPE;Betrag;Status;Verwendungszweck\n\
+            01.10.2021;4;DEPOSIT;-1,00;gebucht;x\n\
+            01.10.2021;5;WITHDRAWAL;-1,00;gebucht;x\n";
+        let mut it = csv_parser::records_with(data.as_bytes(), &options);
+        assert_eq!(
+            it.next().unwrap().unwrap_err().to_string(),
+            "2:22: Invalid number in AMOUNT"
+        );
+        assert_eq!(it.next().unwrap().unwrap().amount.minor(), 100);
+    }
+
+    #[test]
+    fn test_extensions() {
+        let with_extensions = |tx_id: u64, extensions: &[(&str, &str)]| Record {
+            tx_id,
+            description: "x".to_string(),
+            extensions: extensions
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            ..record()
+        };
+        let records = [
+            with_extensions(1, &[("MERCHANT_REF", "M-42"), ("note.free", "a\"b")]),
+            with_extensions(2, &[("note.free", "c")]),
+        ];
+
+        let mut output = Vec::new();
+        csv_parser::write_to(&mut output, records.clone()).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.starts_with(&format!("{},MERCHANT_REF,note.free\n", CSV_HEADER)));
+        assert!(output.ends_with(",x,,c\n"));
+        assert_eq!(csv_parser::read_from(output.as_bytes()).unwrap(), records);
+
+        let mut output = Vec::new();
+        let mut writer = csv_parser::CsvWriter::new(&mut output);
+        writer.write_record(&with_extensions(1, &[])).unwrap();
+        let error = writer
+            .write_record(&with_extensions(2, &[("MERCHANT_REF", "M-42")]))
+            .unwrap_err();
+        assert!(matches!(error, ParseError::InvalidExtension(name) if name == "MERCHANT_REF"));
+        drop(writer);
+        assert_eq!(String::from_utf8(output).unwrap().lines().count(), 2);
+
+        let options = csv_parser::CsvOptions {
+            extensions: Some(vec!["MERCHANT_REF".to_string()]),
+            ..Default::default()
+        };
+        let records = [
+            with_extensions(1, &[]),
+            with_extensions(2, &[("MERCHANT_REF", "M-42")]),
+        ];
+        let mut output = Vec::new();
+        let mut writer = csv_parser::CsvWriter::with_options(&mut output, &options);
+        for record in &records {
+            writer.write_record(record).unwrap();
+        }
+        writer.finish().unwrap();
+        drop(writer);
+        assert!(output.starts_with(format!("{},MERCHANT_REF\n", CSV_HEADER).as_bytes()));
+        assert!(output.ends_with(b",x,\n2,DEPOSIT,0,2,100,1633036860000,SUCCESS,x,M-42\n"));
+        assert_eq!(csv_parser::read_from(&output[..]).unwrap(), records);
+
+        let mut writer = csv_parser::CsvWriter::new(Vec::new());
+        writer
+            .write_record(&with_extensions(1, &[("CHANNEL", "web")]))
+            .unwrap();
+        let error = writer
+            .write_record(&with_extensions(2, &[("CHANNEL", "")]))
+            .unwrap_err();
+        assert!(matches!(error, ParseError::InvalidExtension(name) if name == "CHANNEL"));
+    }
+
+    #[test]
+    fn test_legacy_descriptions() {
+        let data = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n\
+            1,DEPOSIT,0,2,100,1633036860000,SUCCESS,\"Record number 1\"\n\
+            2,DEPOSIT,0,2,100,1633036860000,SUCCESS,\"say \"\"hi\"\", ok\"\n\
+            3,DEPOSIT,0,2,100,1633036860000,SUCCESS,plain\n";
+        let mut options = csv_parser::CsvOptions::default();
+        options.values.description = core::DescriptionQuotes::Legacy;
+
+        let r = csv_parser::records_with(data.as_bytes(), &options).collect::<Result<Vec<_>, _>>();
+        let r = r.unwrap();
+        let descriptions: Vec<&str> = r.iter().map(|r| r.description.as_str()).collect();
+
+        assert_eq!(
+            descriptions,
+            ["\"Record number 1\"", "\"say \"\"hi\"\", ok\"", "plain"]
+        );
+
+        let mut output = Vec::new();
+        let mut writer = csv_parser::CsvWriter::with_options(&mut output, &options);
+        for record in &r {
+            writer.write_record(record).unwrap();
+        }
+        writer.finish().unwrap();
+        drop(writer);
+        assert_eq!(String::from_utf8(output).unwrap(), data);
+    }
 }
@@ -1,5 +1,14 @@
 pub mod bin_format;
 pub mod csv_format;
+mod profile;
+mod registry;
 pub mod text_format;
 
-pub use core::{Format, ParseError, Reader, Record, TransactionStatus, TransactionType};
+pub use core::{
+    Amount, DescriptionQuotes, Diagnostic, ErrorStrategy, Format, ParseError, ParseOptions,
+    ParseReport, Position, Reader, Record, RecordSource, RecordWriter, Rule, TEXT_KEYS, Timestamp,
+    TimestampFormat, TimestampRange, TransactionStatus, TransactionType, ValidationReport,
+    Validator, ValueFormat, Violation, Writer, parse_utc_offset,
+};
+pub use profile::{AmountSpelling, Profile, ProfileFields, TimestampSpelling, Vocabulary};
+pub use registry::{FormatOptions, FormatRegistry, ReaderFactory, Records, WriterFactory};
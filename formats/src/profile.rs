@@ -0,0 +1,300 @@
+use core::{Amount, ParseError, Timestamp, TransactionStatus, TransactionType, parse_utc_offset};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Mapping of a foreign csv layout onto [`core::Record`] fields, loaded from TOML
+///
+/// Every part is optional, [`Profile::default`] reads the standard csv layout.
+///
+/// ```toml
+/// [columns]
+/// tx_id = "Reference"
+/// tx_type = "Kind"
+/// amount = "Amount"
+/// timestamp = "Booking date"
+/// description = "Purpose"
+///
+/// [defaults]
+/// from_user_id = "0"
+/// to_user_id = "0"
+/// status = "SUCCESS"
+///
+/// [values.tx_type]
+/// Credit = "DEPOSIT"
+/// Debit = "WITHDRAWAL"
+///
+/// [amount]
+/// scale = 2
+/// decimal_separator = ","
+/// signed = true
+///
+/// [timestamp]
+/// format = "DD.MM.YYYY"
+/// utc_offset = "+01:00"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    /// Source column of each field, fields left out keep their standard column name
+    pub columns: ProfileFields,
+    /// Value of fields whose column is not in the source
+    pub defaults: ProfileFields,
+    pub values: Vocabulary,
+    pub amount: AmountSpelling,
+    pub timestamp: TimestampSpelling,
+}
+
+/// One optional string for each [`core::Record`] field
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProfileFields {
+    pub tx_id: Option<String>,
+    pub tx_type: Option<String>,
+    pub from_user_id: Option<String>,
+    pub to_user_id: Option<String>,
+    pub amount: Option<String>,
+    pub timestamp: Option<String>,
+    pub status: Option<String>,
+    pub description: Option<String>,
+}
+
+impl ProfileFields {
+    /// Values in the order of the csv columns
+    pub(crate) fn get(&self) -> [Option<&str>; 8] {
+        [
+            &self.tx_id,
+            &self.tx_type,
+            &self.from_user_id,
+            &self.to_user_id,
+            &self.amount,
+            &self.timestamp,
+            &self.status,
+            &self.description,
+        ]
+        .map(Option::as_deref)
+    }
+}
+
+/// Source spellings of values mapped to the standard ones, e.g. `Credit = "DEPOSIT"`
+///
+/// Source values are matched ignoring case, values without a mapping have to be standard.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Vocabulary {
+    pub tx_type: BTreeMap<String, String>,
+    pub status: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AmountSpelling {
+    /// Number of decimals in the source, the amount scale of the csv options when not set
+    pub scale: Option<u8>,
+    pub decimal_separator: char,
+    /// Amounts may start with `-` or `+`
+    ///
+    /// Without a transaction type column or default, negative amounts are
+    /// WITHDRAWAL and the others DEPOSIT. With one only WITHDRAWAL amounts may be
+    /// negative, the sign of others would be lost.
+    pub signed: bool,
+}
+
+impl Default for AmountSpelling {
+    fn default() -> Self {
+        AmountSpelling {
+            scale: None,
+            decimal_separator: '.',
+            signed: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimestampSpelling {
+    /// Pattern like `DD.MM.YYYY`, see [`Timestamp::parse_pattern`],
+    /// epoch milliseconds or RFC 3339 when not set
+    pub format: Option<String>,
+    /// Time zone of source timestamps without one, `Z` or `+HH:MM`/`-HH:MM`
+    pub utc_offset: Option<String>,
+}
+
+impl Profile {
+    /// Parse and check a profile
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let profile = formats::Profile::from_toml("[values.tx_type]\nCredit = \"DEPOSIT\"\n").unwrap();
+    ///
+    /// assert_eq!(profile.values.tx_type["Credit"], "DEPOSIT");
+    /// assert!(formats::Profile::from_toml("[values.tx_type]\nCredit = \"GIFT\"\n").is_err());
+    /// ```
+    pub fn from_toml(toml: &str) -> Result<Profile, ParseError> {
+        let profile: Profile = toml::from_str(toml).map_err(|e| {
+            let line = e
+                .span()
+                .map_or(1, |span| toml[..span.start].matches('\n').count() + 1);
+            ParseError::InvalidProfile(format!("line {}: {}", line, e.message()))
+        })?;
+        profile.check()?;
+        Ok(profile)
+    }
+
+    /// Read a profile from a TOML file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Profile, ParseError> {
+        Profile::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    fn check(&self) -> Result<(), ParseError> {
+        let invalid = |message: String| Err(ParseError::InvalidProfile(message));
+
+        for target in self.values.tx_type.values() {
+            if TransactionType::parse(target).is_err() {
+                return invalid(format!("unknown transaction type '{}'", target));
+            }
+        }
+        for target in self.values.status.values() {
+            if TransactionStatus::parse(target).is_err() {
+                return invalid(format!("unknown transaction status '{}'", target));
+            }
+        }
+        if matches!(self.amount.decimal_separator, '0'..='9' | '-' | '+') {
+            return invalid("decimal separator can not be a digit or sign".to_string());
+        }
+        if let Some(format) = &self.timestamp.format
+            && !["YYYY", "MM", "DD"]
+                .iter()
+                .all(|part| format.contains(part))
+        {
+            return invalid(format!("timestamp format '{}' has no full date", format));
+        }
+        if let Some(offset) = &self.timestamp.utc_offset
+            && parse_utc_offset(offset).is_none()
+        {
+            return invalid(format!("invalid UTC offset '{}'", offset));
+        }
+
+        let fields = [
+            "tx_id",
+            "tx_type",
+            "from_user_id",
+            "to_user_id",
+            "amount",
+            "timestamp",
+            "status",
+            "description",
+        ];
+        for (field, default) in fields.iter().zip(self.defaults.get()) {
+            let Some(value) = default else { continue };
+            let valid = match *field {
+                "tx_id" | "from_user_id" | "to_user_id" => value.parse::<u64>().is_ok(),
+                "tx_type" => self.tx_type(value).is_ok(),
+                "status" => self.status(value).is_ok(),
+                "timestamp" => self.timestamp(value).is_ok(),
+                // without a scale of its own the one of the csv options applies
+                "amount" => self
+                    .amount
+                    .scale
+                    .is_none_or(|scale| self.amount(value, scale).is_ok()),
+                _ => true,
+            };
+            if !valid {
+                return invalid(format!("invalid default {} '{}'", field, value));
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn tx_type(&self, value: &str) -> Result<TransactionType, ParseError> {
+        let value = lookup(&self.values.tx_type, value);
+        Ok(TransactionType::parse(value)?)
+    }
+
+    pub(crate) fn status(&self, value: &str) -> Result<TransactionStatus, ParseError> {
+        let value = lookup(&self.values.status, value);
+        Ok(TransactionStatus::parse(value)?)
+    }
+
+    /// Amount and whether it was negative
+    pub(crate) fn amount(&self, value: &str, scale: u8) -> Result<(Amount, bool), ParseError> {
+        let (negative, value) = match value.as_bytes().first() {
+            Some(b'-') if self.amount.signed => (true, &value[1..]),
+            Some(b'+') if self.amount.signed => (false, &value[1..]),
+            _ => (false, value),
+        };
+        let value = match self.amount.decimal_separator {
+            '.' => value.to_string(),
+            _ if value.contains('.') => return Err(ParseError::InvalidNumber("AMOUNT")),
+            separator => value.replace(separator, "."),
+        };
+
+        Amount::parse(&value, self.amount.scale.unwrap_or(scale))
+            .map(|amount| (amount, negative))
+            .ok_or(ParseError::InvalidNumber("AMOUNT"))
+    }
+
+    pub(crate) fn timestamp(&self, value: &str) -> Result<Timestamp, ParseError> {
+        let offset = self.timestamp.utc_offset.as_deref();
+        let timestamp = match &self.timestamp.format {
+            Some(format) => Timestamp::parse_pattern(
+                value,
+                format,
+                offset.and_then(parse_utc_offset).unwrap_or(0),
+            ),
+            None => Timestamp::parse(value),
+        };
+        timestamp.ok_or(ParseError::InvalidNumber("TIMESTAMP"))
+    }
+}
+
+fn lookup<'a>(vocabulary: &'a BTreeMap<String, String>, value: &'a str) -> &'a str {
+    vocabulary
+        .iter()
+        .find(|(source, _)| source.eq_ignore_ascii_case(value))
+        .map_or(value, |(_, target)| target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_profiles() {
+        let errors: Vec<String> = [
+            "[colums]\ntx_id = \"Reference\"",
+            "[values.status]\nDone = \"OK\"",
+            "[amount]\ndecimal_separator = \"-\"",
+            "[timestamp]\nformat = \"MM/YYYY\"",
+            "[timestamp]\nutc_offset = \"CET\"",
+            "[defaults]\nto_user_id = \"nobody\"",
+            "[defaults]\ntx_type = \"Credit\"",
+            "[defaults]\nstatus = \"DONE\"",
+        ]
+        .iter()
+        .map(|toml| Profile::from_toml(toml).unwrap_err().to_string())
+        .collect();
+
+        assert!(errors[0].starts_with("Invalid profile: line 1: unknown field `colums`"));
+        assert_eq!(
+            errors[1..],
+            [
+                "Invalid profile: unknown transaction status 'OK'",
+                "Invalid profile: decimal separator can not be a digit or sign",
+                "Invalid profile: timestamp format 'MM/YYYY' has no full date",
+                "Invalid profile: invalid UTC offset 'CET'",
+                "Invalid profile: invalid default to_user_id 'nobody'",
+                "Invalid profile: invalid default tx_type 'Credit'",
+                "Invalid profile: invalid default status 'DONE'",
+            ]
+        );
+        assert!(
+            Profile::from_toml(
+                "[values.tx_type]\nCredit = \"DEPOSIT\"\n[defaults]\ntx_type = \"Credit\""
+            )
+            .is_ok()
+        );
+        assert_eq!(Profile::from_toml("").unwrap(), Profile::default());
+    }
+}
@@ -0,0 +1,229 @@
+use core::{Format, ParseError, Reader, RecordSource, RecordWriter, Writer};
+use std::io::{Read, Write};
+use std::path::Path;
+
+use crate::bin_format::bin_parser::{self, BinOptions, BinWriter};
+use crate::csv_format::csv_parser::{self, CsvOptions, CsvWriter};
+use crate::text_format::text_parser::{self, TextOptions, TextWriter};
+
+pub type Records = Box<dyn RecordSource>;
+pub type ReaderFactory = Box<dyn Fn(Box<dyn Read>) -> Records>;
+pub type WriterFactory = Box<dyn Fn(Box<dyn Write>) -> Box<dyn RecordWriter>>;
+
+struct FormatEntry {
+    name: String,
+    extensions: Vec<String>,
+    reader: ReaderFactory,
+    writer: WriterFactory,
+}
+
+/// Set of formats available at runtime, looked up by name or file extension
+///
+/// [`FormatRegistry::default`] contains the built-in `bin`, `csv` and `text`
+/// formats, more can be added with [`FormatRegistry::register`].
+///
+/// # Examples
+///
+/// ```
+/// let registry = formats::FormatRegistry::default();
+/// let data = b"TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n1000000000000000,DEPOSIT,0,9223372036854775807,100,1633036860000,FAILURE,\"Record number 1\"\n";
+///
+/// let name = registry.name_for_path("records.csv").unwrap();
+/// let r: Vec<_> = registry.records(name, &data[..]).unwrap().collect();
+///
+/// assert_eq!(r.len(), 1);
+/// ```
+pub struct FormatRegistry {
+    formats: Vec<FormatEntry>,
+}
+
+impl FormatRegistry {
+    /// Registry without any format
+    pub fn new() -> Self {
+        FormatRegistry {
+            formats: Vec::new(),
+        }
+    }
+
+    /// Register a format under `name`, replacing a format registered earlier with the same name
+    pub fn register<RF, WF>(&mut self, name: &str, extensions: &[&str], reader: RF, writer: WF)
+    where
+        RF: Fn(Box<dyn Read>) -> Records + 'static,
+        WF: Fn(Box<dyn Write>) -> Box<dyn RecordWriter> + 'static,
+    {
+        self.formats.retain(|f| !f.name.eq_ignore_ascii_case(name));
+        self.formats.push(FormatEntry {
+            name: name.to_string(),
+            extensions: extensions.iter().map(|e| e.to_string()).collect(),
+            reader: Box::new(reader),
+            writer: Box::new(writer),
+        });
+    }
+
+    /// Register a type implementing both [`Reader`] and [`Writer`]
+    pub fn register_format<F: Reader + Writer + 'static>(
+        &mut self,
+        name: &str,
+        extensions: &[&str],
+    ) {
+        self.register(
+            name,
+            extensions,
+            |r| Box::new(F::records(r)),
+            |w| Box::new(F::writer(w)),
+        );
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.formats.iter().map(|f| f.name.as_str())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.entry(name).is_ok()
+    }
+
+    /// Name of the format registered for the extension of `path`
+    pub fn name_for_path<P: AsRef<Path>>(&self, path: P) -> Option<&str> {
+        let extension = path.as_ref().extension()?.to_str()?;
+
+        self.formats
+            .iter()
+            .find(|f| {
+                f.extensions
+                    .iter()
+                    .any(|e| e.eq_ignore_ascii_case(extension))
+            })
+            .map(|f| f.name.as_str())
+    }
+
+    /// Lazily read transactions of the format `name` from any reader
+    pub fn records<R: Read + 'static>(&self, name: &str, r: R) -> Result<Records, ParseError> {
+        Ok((self.entry(name)?.reader)(Box::new(r)))
+    }
+
+    /// Create an incremental writer of the format `name` over any writer
+    pub fn writer<W: Write + 'static>(
+        &self,
+        name: &str,
+        w: W,
+    ) -> Result<Box<dyn RecordWriter>, ParseError> {
+        Ok((self.entry(name)?.writer)(Box::new(w)))
+    }
+
+    fn entry(&self, name: &str) -> Result<&FormatEntry, ParseError> {
+        self.formats
+            .iter()
+            .find(|f| f.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| ParseError::UnknownFormat(name.to_string()))
+    }
+}
+
+/// Options of the built-in formats
+#[derive(Debug, Clone, Default)]
+pub struct FormatOptions {
+    pub bin: BinOptions,
+    pub csv: CsvOptions,
+    pub text: TextOptions,
+}
+
+impl FormatRegistry {
+    /// Registry with the built-in formats configured by `options`
+    pub fn with_options(options: &FormatOptions) -> Self {
+        let mut registry = FormatRegistry::new();
+        let (bin_reader, bin_writer) = (options.bin.clone(), options.bin.clone());
+        registry.register(
+            Format::Bin.name(),
+            &["bin"],
+            move |r| Box::new(bin_parser::records_with(r, &bin_reader)),
+            move |w| Box::new(BinWriter::with_options(w, &bin_writer)),
+        );
+        let (csv_reader, csv_writer) = (options.csv.clone(), options.csv.clone());
+        registry.register(
+            Format::Csv.name(),
+            &["csv"],
+            move |r| Box::new(csv_parser::records_with(r, &csv_reader)),
+            move |w| Box::new(CsvWriter::with_options(w, &csv_writer)),
+        );
+        let (text_reader, text_writer) = (options.text.clone(), options.text.clone());
+        registry.register(
+            Format::Text.name(),
+            &["txt", "text"],
+            move |r| Box::new(text_parser::records_with(r, &text_reader)),
+            move |w| Box::new(TextWriter::with_options(w, &text_writer)),
+        );
+        registry
+    }
+}
+
+impl Default for FormatRegistry {
+    fn default() -> Self {
+        FormatRegistry::with_options(&FormatOptions::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Record the conversion tests start from
+    fn record() -> core::Record {
+        core::Record {
+            tx_id: 1,
+            tx_type: core::TransactionType::Deposit,
+            from_user_id: 0,
+            to_user_id: 2,
+            amount: 100.into(),
+            timestamp: 1633036860000.into(),
+            status: core::TransactionStatus::Success,
+            description: "Record number 1".to_string(),
+            extensions: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_builtin_formats() {
+        let registry = FormatRegistry::default();
+
+        assert_eq!(registry.names().collect::<Vec<_>>(), ["bin", "csv", "text"]);
+        assert_eq!(registry.name_for_path("records_example.txt"), Some("text"));
+        assert_eq!(registry.name_for_path("records_example.BIN"), Some("bin"));
+        assert_eq!(registry.name_for_path("records_example"), None);
+        assert!(registry.contains("CSV"));
+    }
+
+    #[test]
+    fn test_register_custom_format() {
+        let mut registry = FormatRegistry::default();
+        registry.register(
+            "empty",
+            &["empty"],
+            |r| Box::new(crate::csv_format::csv_parser::records(r)),
+            |w| Box::new(crate::csv_format::csv_parser::CsvWriter::new(w)),
+        );
+
+        assert_eq!(registry.records("EMPTY", &b""[..]).unwrap().count(), 0);
+        assert!(matches!(
+            registry.records("xml", &b""[..]),
+            Err(ParseError::UnknownFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_extensions_survive_conversion() {
+        use crate::{bin_format::bin_parser, text_format::text_parser};
+
+        let record = core::Record {
+            extensions: [("MERCHANT_REF".to_string(), "M-42".to_string())].into(),
+            ..record()
+        };
+
+        let mut bin = Vec::new();
+        bin_parser::write_to(&mut bin, [record.clone()]).unwrap();
+        let mut csv = Vec::new();
+        csv_parser::write_to(&mut csv, bin_parser::read_from(&bin[..]).unwrap()).unwrap();
+        let mut text = Vec::new();
+        text_parser::write_to(&mut text, csv_parser::read_from(&csv[..]).unwrap()).unwrap();
+
+        assert_eq!(text_parser::read_from(&text[..]).unwrap(), [record]);
+    }
+}
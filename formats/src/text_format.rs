@@ -1,12 +1,22 @@
 pub mod text_parser {
-    use core::{ParseError, Reader, Record, TextRecordDraft, TransactionStatus, TransactionType};
+    use core::{
+        Amount, DescriptionQuotes, ParseError, ParseOptions, ParseReport, Position, Reader, Record,
+        RecordSource, RecordWriter, TEXT_KEYS, TextRecordDraft, Timestamp, TransactionStatus,
+        TransactionType, ValueFormat, Writer,
+    };
     use std::io::{self, BufRead, BufWriter, Write};
 
     pub struct Text;
 
     impl Reader for Text {
-        fn read_from(file: std::fs::File) -> Result<Vec<Record>, ParseError> {
-            self::read_from(file)
+        fn records<R: std::io::Read>(r: R) -> impl RecordSource {
+            self::records(r)
+        }
+    }
+
+    impl Writer for Text {
+        fn writer<W: std::io::Write>(w: W) -> impl RecordWriter {
+            TextWriter::new(w)
         }
     }
 
@@ -31,58 +41,344 @@ pub mod text_parser {
     /// assert_eq!(r.len(), 1);
     /// ```
     pub fn read_from<R: std::io::Read>(r: R) -> Result<Vec<Record>, ParseError> {
-        let reader = io::BufReader::new(r);
-        let mut data: Vec<Record> = Vec::new();
-
-        let mut draft = TextRecordDraft {
-            tx_id: None,
-            tx_type: None,
-            to_user_id: None,
-            from_user_id: None,
-            amount: None,
-            timestamp: None,
-            description: None,
-            status: None,
-        };
+        records(r).collect()
+    }
+
+    /// Lazily read transactions from text format one Record at a time
+    ///
+    /// Keys other than the standard ones are kept as [`Record::extensions`], a key repeated
+    /// in one record is an error, see [`TextMode`] for a more forgiving reader. A description
+    /// or extension value in quotes is read without them, inside `\"`, `\\`, `\n`, `\r`, `\t`
+    /// and `\u{1b}` stand for a quote, a backslash, line breaks, a tab and any other character,
+    /// so values can span several lines.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let data = "# Record 1 (DEPOSIT)
+    ///     TX_TYPE: DEPOSIT
+    ///     TO_USER_ID: 9223372036854775807
+    ///     FROM_USER_ID: 0
+    ///     TIMESTAMP: 1633036860000
+    ///     DESCRIPTION: \"Record number 1\"
+    ///     TX_ID: 1000000000000000
+    ///     AMOUNT: 100
+    ///     STATUS: FAILURE";
+    ///
+    /// let cursor = std::io::Cursor::new(data);
+    /// let mut it = formats::text_format::text_parser::records(cursor);
+    ///
+    /// assert_eq!(it.next().unwrap().unwrap().amount.minor(), 100);
+    /// assert!(it.next().is_none());
+    /// ```
+    pub fn records<R: std::io::Read>(r: R) -> TextRecords<R> {
+        records_with(r, &TextOptions::default())
+    }
+
+    /// How keys of text records are read
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub enum TextMode {
+        /// Standard keys in upper case, a key repeated in one record is an error
+        #[default]
+        Strict,
+        /// Standard keys in any case, a repeated key replaces the earlier value
+        /// and keys that can not be extension fields are ignored
+        Permissive,
+    }
+
+    /// Options of the text format
+    #[derive(Debug, Clone)]
+    pub struct TextOptions {
+        pub values: ValueFormat,
+        pub mode: TextMode,
+        /// Order of the fields in written records, every one of [`TEXT_KEYS`] once
+        pub field_order: [&'static str; 8],
+    }
+
+    impl Default for TextOptions {
+        fn default() -> Self {
+            TextOptions {
+                values: ValueFormat::default(),
+                mode: TextMode::default(),
+                field_order: TEXT_KEYS,
+            }
+        }
+    }
+
+    /// Field order from comma separated keys in any case, every key has to be named once
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use formats::text_format::text_parser::parse_field_order;
+    ///
+    /// let order = parse_field_order("tx_type,to_user_id,from_user_id,timestamp,description,tx_id,amount,status");
+    ///
+    /// assert_eq!(order.unwrap()[0], "TX_TYPE");
+    /// assert!(parse_field_order("TX_ID,TX_TYPE").is_err());
+    /// ```
+    pub fn parse_field_order(value: &str) -> Result<[&'static str; 8], ParseError> {
+        let keys = value
+            .split(',')
+            .map(|name| {
+                TEXT_KEYS
+                    .into_iter()
+                    .find(|key| key.eq_ignore_ascii_case(name.trim()))
+            })
+            .collect::<Option<Vec<_>>>();
+
+        match keys.and_then(|keys| <[&str; 8]>::try_from(keys).ok()) {
+            Some(order) if is_field_order(&order) => Ok(order),
+            _ => Err(ParseError::InvalidArgument(
+                "text field order must name every field once",
+            )),
+        }
+    }
+
+    fn is_field_order(order: &[&str; 8]) -> bool {
+        TEXT_KEYS.iter().all(|key| order.contains(key))
+    }
+
+    /// Lazily read transactions from text format configured by `options`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use formats::text_format::text_parser::{self, TextOptions};
+    ///
+    /// let data = "TX_ID: 1\nTX_TYPE: DEPOSIT\nFROM_USER_ID: 0\nTO_USER_ID: 2\nAMOUNT: 0.5\nTIMESTAMP: 1633036860000\nSTATUS: SUCCESS\nDESCRIPTION: x\n";
+    ///
+    /// let mut options = TextOptions::default();
+    /// options.values.amount_scale = 2;
+    /// let mut it = text_parser::records_with(data.as_bytes(), &options);
+    ///
+    /// assert_eq!(it.next().unwrap().unwrap().amount.minor(), 50);
+    /// ```
+    pub fn records_with<R: std::io::Read>(r: R, options: &TextOptions) -> TextRecords<R> {
+        TextRecords {
+            lines: io::BufReader::new(r).lines(),
+            options: options.clone(),
+            draft: TextRecordDraft::default(),
+            raw: String::new(),
+            line: 0,
+            record_line: 0,
+            done: false,
+            fatal: false,
+        }
+    }
+
+    /// Read transactions from text format handling broken records according to `options`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let data = "TX_ID: 1\nAMOUNT: ten\n\nTX_TYPE: DEPOSIT
+    ///     TO_USER_ID: 9223372036854775807
+    ///     FROM_USER_ID: 0
+    ///     TIMESTAMP: 1633036860000
+    ///     DESCRIPTION: \"Record number 1\"
+    ///     TX_ID: 1000000000000000
+    ///     AMOUNT: 100
+    ///     STATUS: FAILURE";
+    ///
+    /// let options = formats::ParseOptions::new(formats::ErrorStrategy::Skip);
+    /// let cursor = std::io::Cursor::new(data);
+    /// let r = formats::text_format::text_parser::read_with(cursor, &options).unwrap();
+    ///
+    /// assert_eq!(r.records.len(), 1);
+    /// assert!(r.diagnostics.is_empty());
+    /// ```
+    pub fn read_with<R: std::io::Read>(
+        r: R,
+        options: &ParseOptions,
+    ) -> Result<ParseReport, ParseError> {
+        options.read(records(r))
+    }
 
-        for line in reader.lines() {
-            let line = line.map_err(ParseError::Io)?;
-            let line = line.trim();
+    /// Iterator over the records of a text stream, see [`records`]
+    pub struct TextRecords<R: std::io::Read> {
+        lines: io::Lines<io::BufReader<R>>,
+        options: TextOptions,
+        draft: TextRecordDraft,
+        raw: String,
+        line: usize,
+        record_line: usize,
+        done: bool,
+        /// The last error ended the reading
+        fatal: bool,
+    }
+
+    impl<R: std::io::Read> Iterator for TextRecords<R> {
+        type Item = Result<Record, ParseError>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            if self.done {
+                return None;
+            }
+
+            self.read_record().transpose()
+        }
+    }
+
+    impl<R: std::io::Read> RecordSource for TextRecords<R> {
+        fn raw(&self) -> &[u8] {
+            self.raw.as_bytes()
+        }
+
+        fn can_continue(&self) -> bool {
+            !self.fatal
+        }
+    }
+
+    impl<R: std::io::Read> TextRecords<R> {
+        /// Read lines up to the end of the next record, the first error of a record is kept
+        /// until all its lines are consumed so reading can go on with the next one
+        fn read_record(&mut self) -> Result<Option<Record>, ParseError> {
+            let draft = &mut self.draft;
+            let mut error: Option<ParseError> = None;
+            self.raw.clear();
+
+            loop {
+                let raw = match self.lines.next() {
+                    Some(raw) => raw,
+                    None => {
+                        self.done = true;
+                        break;
+                    }
+                };
+                self.line += 1;
+                let position = Position::Line {
+                    line: self.line,
+                    column: None,
+                };
+                let raw = match raw {
+                    Ok(raw) => raw,
+                    Err(e) => {
+                        (self.done, self.fatal) = (true, true);
+                        return Err(ParseError::Io(e).at(position));
+                    }
+                };
+                let line = raw.trim();
+
+                if line.is_empty() && (!draft.is_empty() || error.is_some()) {
+                    break;
+                } else if line.is_empty() {
+                    continue;
+                }
+
+                self.raw.push_str(&raw);
+                self.raw.push('\n');
+
+                if line.starts_with('#') || error.is_some() {
+                    continue;
+                }
+
+                if draft.is_empty() {
+                    self.record_line = self.line;
+                }
 
-            if line.is_empty() && !draft.is_empty() {
-                let record = Record::from_draft(&draft)?;
-                data.push(record);
-                draft.reset();
-                continue;
-            } else if line.is_empty() {
-                continue;
+                let Some(colon) = raw.find(':') else {
+                    error = Some(ParseError::MalformedLine.at(position));
+                    continue;
+                };
+                let (key, rest) = (raw[..colon].trim(), &raw[colon + 1..]);
+                if let Err(e) = set_field(draft, key, rest.trim(), &self.options) {
+                    // duplicates point at the key, other errors at the value
+                    let column = match e {
+                        ParseError::DuplicateKey(_) => raw.len() - raw.trim_start().len() + 1,
+                        _ => colon + 2 + rest.len() - rest.trim_start().len(),
+                    };
+                    error = Some(e.at(Position::Line {
+                        line: self.line,
+                        column: Some(column),
+                    }));
+                }
             }
 
-            if line.starts_with('#') {
-                continue;
+            if draft.is_empty() && error.is_none() {
+                return Ok(None);
             }
 
-            let mut it = line.splitn(2, ':').map(|s| s.trim());
-            let key = it.next().ok_or(ParseError::MalformedLine)?;
-            let value = it.next().ok_or(ParseError::MalformedLine)?;
+            let record = match error {
+                Some(e) => Err(e),
+                None => Record::from_draft(draft).map_err(|e| {
+                    e.at(Position::Line {
+                        line: self.record_line,
+                        column: None,
+                    })
+                }),
+            };
+            draft.reset();
+            record.map(Some)
+        }
+    }
 
-            match key {
-                "TX_ID" => draft.tx_id = Some(value.parse().unwrap()),
-                "FROM_USER_ID" => draft.from_user_id = Some(parse_str(value)?),
-                "TO_USER_ID" => draft.to_user_id = Some(parse_str(value)?),
-                "TIMESTAMP" => draft.timestamp = Some(parse_str(value)?),
-                "AMOUNT" => draft.amount = Some(parse_str(value)?),
-                "TX_TYPE" => draft.tx_type = Some(TransactionType::parse(value)?),
-                "DESCRIPTION" => draft.description = Some(value.to_string()),
-                "STATUS" => draft.status = Some(TransactionStatus::parse(value)?),
-                _ => return Err(ParseError::MalformedLine),
+    /// Standard key spelled by `key` in the given mode
+    pub(super) fn standard_key(key: &str, mode: TextMode) -> Option<&'static str> {
+        TEXT_KEYS.into_iter().find(|standard| match mode {
+            TextMode::Strict => *standard == key,
+            TextMode::Permissive => standard.eq_ignore_ascii_case(key),
+        })
+    }
+
+    pub(super) fn set_field(
+        draft: &mut TextRecordDraft,
+        key: &str,
+        value: &str,
+        options: &TextOptions,
+    ) -> Result<(), ParseError> {
+        let values = &options.values;
+        let standard = standard_key(key, options.mode);
+        if options.mode == TextMode::Strict {
+            let repeated = match standard {
+                Some("TX_ID") => draft.tx_id.is_some(),
+                Some("TX_TYPE") => draft.tx_type.is_some(),
+                Some("FROM_USER_ID") => draft.from_user_id.is_some(),
+                Some("TO_USER_ID") => draft.to_user_id.is_some(),
+                Some("AMOUNT") => draft.amount.is_some(),
+                Some("TIMESTAMP") => draft.timestamp.is_some(),
+                Some("STATUS") => draft.status.is_some(),
+                Some(_) => draft.description.is_some(),
+                None => draft.extensions.contains_key(key),
+            };
+            if repeated {
+                return Err(ParseError::DuplicateKey(key.to_string()));
             }
         }
-        if !draft.is_empty() {
-            let record = Record::from_draft(&draft)?;
-            data.push(record);
+
+        match standard.unwrap_or(key) {
+            "TX_ID" => draft.tx_id = Some(parse_str(value, "TX_ID")?),
+            "FROM_USER_ID" => draft.from_user_id = Some(parse_str(value, "FROM_USER_ID")?),
+            "TO_USER_ID" => draft.to_user_id = Some(parse_str(value, "TO_USER_ID")?),
+            "TIMESTAMP" => {
+                draft.timestamp =
+                    Some(Timestamp::parse(value).ok_or(ParseError::InvalidNumber("TIMESTAMP"))?)
+            }
+            "AMOUNT" => {
+                draft.amount = Some(
+                    Amount::parse(value, values.amount_scale)
+                        .ok_or(ParseError::InvalidNumber("AMOUNT"))?,
+                )
+            }
+            "TX_TYPE" => draft.tx_type = Some(TransactionType::parse(value)?),
+            "DESCRIPTION" => {
+                draft.description = Some(match values.description {
+                    DescriptionQuotes::Syntax => unquote(value)?,
+                    DescriptionQuotes::Legacy => value.to_string(),
+                })
+            }
+            "STATUS" => draft.status = Some(TransactionStatus::parse(value)?),
+            key if Record::is_extension_name(key) => {
+                let value = match values.description {
+                    DescriptionQuotes::Syntax => unquote(value)?,
+                    DescriptionQuotes::Legacy => value.to_string(),
+                };
+                draft.extensions.insert(key.to_string(), value);
+            }
+            _ if options.mode == TextMode::Permissive => {}
+            _ => return Err(ParseError::MalformedLine),
         }
-        Ok(data)
+        Ok(())
     }
 
     /// Write transactions of Record entity to csv format
@@ -98,10 +394,11 @@ pub mod text_parser {
     ///     tx_type: core::TransactionType::Deposit,
     ///     from_user_id: 0,
     ///     to_user_id: 9223372036854775807,
-    ///     amount: 100,
-    ///     timestamp: 1633036860000,
+    ///     amount: core::Amount::from(100),
+    ///     timestamp: core::Timestamp::from(1633036860000),
     ///     status: core::TransactionStatus::Failure,
     ///     description: String::from("\"Record number 1\""),
+    ///     extensions: Default::default(),
     /// }];
     ///
     /// let mut cursor = std::io::Cursor::new(Vec::new());
@@ -112,47 +409,446 @@ pub mod text_parser {
     /// cursor.set_position(0);
     /// let lines: Vec<String> = std::io::BufReader::new(cursor).lines().collect::<Result<_, _>>().unwrap();
     ///
-    /// assert_eq!(lines.len(), 8);
+    /// assert_eq!(lines.len(), 9);
     /// ```
-    pub fn write_to<W: std::io::Write>(
+    pub fn write_to<W: std::io::Write, I: IntoIterator<Item = Record>>(
         writer: &mut W,
-        records: Vec<Record>,
+        records: I,
     ) -> Result<(), ParseError> {
-        let mut buffer = BufWriter::new(writer);
-        let mut data = String::new();
+        let mut text_writer = TextWriter::new(writer);
 
         for record in records {
-            let s = format!(
-                "TX_ID: {}\nTX_TYPE: {}\nTO_USER_ID: {}\nFROM_USER_ID: {}\nTIMESTAMP: {}\nDESCRIPTION: {}\nAMOUNT: {}\nSTATUS: {}\n\n",
-                record.tx_id,
-                TransactionType::to_str(&record.tx_type),
-                record.to_user_id,
-                record.from_user_id,
-                record.timestamp,
-                record.description,
-                record.amount,
-                TransactionStatus::to_str(&record.status)
-            );
-            data.push_str(&s);
-        }
-
-        write!(buffer, "{}", data.trim())?;
+            text_writer.write_record(&record)?;
+        }
 
-        Ok(())
+        text_writer.finish()
+    }
+
+    /// Incremental writer of Record entities to text format
+    ///
+    /// Every record starts with a `# Record N (TYPE)` comment like in `records_example.txt`,
+    /// its fields follow in [`TextOptions::field_order`] and records are separated by a
+    /// blank line. Descriptions are written in quotes and extension fields follow the
+    /// standard ones as `NAME: value` lines, quoted when they would not be read back as
+    /// they are. Line breaks and other control characters in quotes are escaped, so every
+    /// record round-trips. With legacy descriptions values with line breaks are rejected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use formats::RecordWriter;
+    ///
+    /// let record = core::Record {
+    ///     tx_id: 1000000000000000,
+    ///     tx_type: core::TransactionType::Deposit,
+    ///     from_user_id: 0,
+    ///     to_user_id: 9223372036854775807,
+    ///     amount: core::Amount::from(100),
+    ///     timestamp: core::Timestamp::from(1633036860000),
+    ///     status: core::TransactionStatus::Failure,
+    ///     description: String::from("\"Record number 1\""),
+    ///     extensions: Default::default(),
+    /// };
+    ///
+    /// let mut output = Vec::new();
+    /// let mut writer = formats::text_format::text_parser::TextWriter::new(&mut output);
+    ///
+    /// writer.write_record(&record).unwrap();
+    /// writer.write_record(&record).unwrap();
+    /// writer.finish().unwrap();
+    /// drop(writer);
+    ///
+    /// assert_eq!(String::from_utf8(output).unwrap().lines().count(), 19);
+    /// ```
+    pub struct TextWriter<W: std::io::Write> {
+        buffer: BufWriter<W>,
+        options: TextOptions,
+        written: usize,
+    }
+
+    impl<W: std::io::Write> TextWriter<W> {
+        pub fn new(writer: W) -> Self {
+            TextWriter::with_options(writer, &TextOptions::default())
+        }
+
+        pub fn with_options(writer: W, options: &TextOptions) -> Self {
+            TextWriter {
+                buffer: BufWriter::new(writer),
+                options: options.clone(),
+                written: 0,
+            }
+        }
+    }
+
+    impl<W: std::io::Write> RecordWriter for TextWriter<W> {
+        fn write_record(&mut self, record: &Record) -> Result<(), ParseError> {
+            if let Some(name) = record.invalid_extension() {
+                return Err(ParseError::InvalidExtension(name.to_string()));
+            }
+            if !is_field_order(&self.options.field_order) {
+                return Err(ParseError::InvalidArgument(
+                    "text field order must name every field once",
+                ));
+            }
+            let values = &self.options.values;
+            if values.description == DescriptionQuotes::Legacy
+                && std::iter::once(&record.description)
+                    .chain(record.extensions.values())
+                    .any(|value| value.contains(['\n', '\r']))
+            {
+                return Err(ParseError::InvalidArgument(
+                    "values with line breaks can not be written with legacy descriptions",
+                ));
+            }
+            if self.written > 0 {
+                writeln!(self.buffer)?;
+            }
+
+            let tx_type = TransactionType::to_str(&record.tx_type);
+            writeln!(self.buffer, "# Record {} ({})", self.written + 1, tx_type)?;
+            for key in self.options.field_order {
+                let value = match key {
+                    "TX_ID" => record.tx_id.to_string(),
+                    "TX_TYPE" => tx_type.to_string(),
+                    "FROM_USER_ID" => record.from_user_id.to_string(),
+                    "TO_USER_ID" => record.to_user_id.to_string(),
+                    "AMOUNT" => record.amount.format(values.amount_scale),
+                    "TIMESTAMP" => values.timestamp.format(record.timestamp),
+                    "STATUS" => TransactionStatus::to_str(&record.status).to_string(),
+                    _ => match values.description {
+                        DescriptionQuotes::Syntax => quote(&record.description),
+                        DescriptionQuotes::Legacy => record.description.clone(),
+                    },
+                };
+                writeln!(self.buffer, "{}: {}", key, value)?;
+            }
+            for (name, value) in &record.extensions {
+                match values.description {
+                    DescriptionQuotes::Syntax if needs_quotes(value) => {
+                        writeln!(self.buffer, "{}: {}", name, quote(value))?
+                    }
+                    _ => writeln!(self.buffer, "{}: {}", name, value)?,
+                }
+            }
+            self.written += 1;
+
+            Ok(())
+        }
+
+        fn finish(&mut self) -> Result<(), ParseError> {
+            self.buffer.flush()?;
+            Ok(())
+        }
+    }
+
+    /// Value in quotes, with quotes, backslashes and control characters inside escaped
+    pub(super) fn quote(value: &str) -> String {
+        let mut quoted = String::with_capacity(value.len() + 2);
+        quoted.push('"');
+        for c in value.chars() {
+            match c {
+                '"' => quoted.push_str("\\\""),
+                '\\' => quoted.push_str("\\\\"),
+                '\n' => quoted.push_str("\\n"),
+                '\r' => quoted.push_str("\\r"),
+                '\t' => quoted.push_str("\\t"),
+                c if c.is_control() => quoted.push_str(&format!("\\u{{{:x}}}", c as u32)),
+                c => quoted.push(c),
+            }
+        }
+        quoted.push('"');
+        quoted
     }
 
-    fn parse_str(value: &str) -> Result<u64, ParseError> {
-        value.parse().map_err(|_| ParseError::InvalidNumber)
+    /// Whether an unquoted value would be read back differently
+    pub(super) fn needs_quotes(value: &str) -> bool {
+        value.starts_with('"') || value.trim() != value || value.chars().any(char::is_control)
+    }
+
+    /// Value of a quoted description, an unquoted one is taken as it is
+    fn unquote(value: &str) -> Result<String, ParseError> {
+        let Some(quoted) = value.strip_prefix('"') else {
+            return Ok(value.to_string());
+        };
+
+        let mut unquoted = String::with_capacity(quoted.len());
+        let mut chars = quoted.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some(escaped @ ('"' | '\\')) => unquoted.push(escaped),
+                    Some('n') => unquoted.push('\n'),
+                    Some('r') => unquoted.push('\r'),
+                    Some('t') => unquoted.push('\t'),
+                    Some('u') => unquoted.push(unescape_unicode(&mut chars)?),
+                    _ => return Err(ParseError::MalformedLine),
+                },
+                // nothing may follow the closing quote
+                '"' if chars.as_str().is_empty() => return Ok(unquoted),
+                '"' => return Err(ParseError::MalformedLine),
+                c => unquoted.push(c),
+            }
+        }
+
+        Err(ParseError::MalformedLine)
+    }
+
+    /// Character of a `\u{..}` escape, `chars` starts after the `u`
+    fn unescape_unicode(chars: &mut std::str::Chars<'_>) -> Result<char, ParseError> {
+        let rest = chars.as_str();
+        let hex = rest
+            .strip_prefix('{')
+            .and_then(|rest| rest.split_once('}'))
+            .map(|(hex, _)| hex)
+            .filter(|hex| {
+                (1..=6).contains(&hex.len()) && hex.chars().all(|c| c.is_ascii_hexdigit())
+            })
+            .ok_or(ParseError::MalformedLine)?;
+        let c = u32::from_str_radix(hex, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or(ParseError::MalformedLine)?;
+        *chars = rest[hex.len() + 2..].chars();
+        Ok(c)
+    }
+
+    fn parse_str(value: &str, field: &'static str) -> Result<u64, ParseError> {
+        value.parse().map_err(|_| ParseError::InvalidNumber(field))
+    }
+}
+
+pub mod text_document {
+    use super::text_parser::{self, TextOptions};
+    use core::{DescriptionQuotes, ParseError, Record, TextRecordDraft};
+    use std::fmt::Display;
+    use std::io::{Read, Write};
+    use std::ops::Range;
+
+    /// Text file kept line by line, so comments, blank lines, spacing and the order of
+    /// fields survive editing
+    ///
+    /// Records are grouped like [`text_parser::records_with`] does, record `n` of the
+    /// document is record `n` read from it. Only lines of edited fields change.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use formats::text_format::{text_document::TextDocument, text_parser::TextOptions};
+    ///
+    /// let data = "# checked by ops\nTX_ID:   1\n\n# was FAILURE\nTX_ID: 2\nSTATUS: PENDING\n";
+    /// let mut document = TextDocument::parse(data, &TextOptions::default());
+    ///
+    /// document.set(1, "STATUS", "SUCCESS").unwrap();
+    /// document.set(0, "AMOUNT", "100").unwrap();
+    ///
+    /// assert_eq!(document.get(0, "TX_ID"), Some("1"));
+    /// assert_eq!(
+    ///     document.to_string(),
+    ///     "# checked by ops\nTX_ID:   1\nAMOUNT: 100\n\n# was FAILURE\nTX_ID: 2\nSTATUS: SUCCESS\n"
+    /// );
+    /// ```
+    #[derive(Debug, Clone)]
+    pub struct TextDocument {
+        /// Lines with their line endings
+        lines: Vec<String>,
+        /// Line range of each record
+        records: Vec<Range<usize>>,
+        options: TextOptions,
+    }
+
+    impl TextDocument {
+        pub fn parse(text: &str, options: &TextOptions) -> TextDocument {
+            let lines: Vec<String> = text.split_inclusive('\n').map(String::from).collect();
+            let mut records = Vec::new();
+            let mut start: Option<usize> = None;
+            let mut has_fields = false;
+
+            for (index, line) in lines.iter().enumerate() {
+                let content = line.trim();
+                if content.is_empty() {
+                    if let Some(start) = start.take_if(|_| has_fields) {
+                        records.push(start..index);
+                        has_fields = false;
+                    }
+                    continue;
+                }
+                start.get_or_insert(index);
+                has_fields |= !content.starts_with('#');
+            }
+            if let Some(start) = start.take_if(|_| has_fields) {
+                records.push(start..lines.len());
+            }
+
+            TextDocument {
+                lines,
+                records,
+                options: options.clone(),
+            }
+        }
+
+        /// Read a whole text file into a document
+        pub fn read_from<R: Read>(
+            mut r: R,
+            options: &TextOptions,
+        ) -> Result<TextDocument, ParseError> {
+            let mut bytes = Vec::new();
+            r.read_to_end(&mut bytes)?;
+            let text = String::from_utf8(bytes).map_err(ParseError::InvalidUtf8)?;
+            Ok(TextDocument::parse(&text, options))
+        }
+
+        pub fn write_to<W: Write>(&self, w: &mut W) -> Result<(), ParseError> {
+            for line in &self.lines {
+                w.write_all(line.as_bytes())?;
+            }
+            w.flush()?;
+            Ok(())
+        }
+
+        /// Number of records
+        pub fn len(&self) -> usize {
+            self.records.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.records.is_empty()
+        }
+
+        /// Records of the document as the text reader reads them
+        pub fn records(&self) -> Vec<Result<Record, ParseError>> {
+            let text = self.to_string();
+            text_parser::records_with(text.as_bytes(), &self.options).collect()
+        }
+
+        /// Value of a field of a record as it is written, e.g. with quotes
+        pub fn get(&self, record: usize, key: &str) -> Option<&str> {
+            let line = self.find(record, key)?;
+            let (_, value) = self.lines[line].split_once(':')?;
+            Some(value.trim())
+        }
+
+        /// Set a field of a record, a missing field is added after the last one
+        ///
+        /// `value` is written the way [`text_parser::TextWriter`] writes it, descriptions
+        /// and extension values are quoted. Values the text reader would reject are errors
+        /// and leave the document unchanged.
+        pub fn set(&mut self, record: usize, key: &str, value: &str) -> Result<(), ParseError> {
+            let range = self
+                .records
+                .get(record)
+                .cloned()
+                .ok_or(ParseError::InvalidArgument(
+                    "document has no record with this index",
+                ))?;
+            let standard = text_parser::standard_key(key, self.options.mode);
+            if standard.is_none() && !Record::is_extension_name(key) {
+                return Err(ParseError::InvalidExtension(key.to_string()));
+            }
+            let value = self.encode(standard, value)?;
+            text_parser::set_field(&mut TextRecordDraft::default(), key, &value, &self.options)?;
+
+            if let Some(index) = self.find(record, key) {
+                let line = &self.lines[index];
+                let content = line.trim_end();
+                let (name, rest) = content.split_once(':').unwrap_or((content, ""));
+                let spacing = match &rest[..rest.len() - rest.trim_start().len()] {
+                    "" => " ",
+                    spacing => spacing,
+                };
+                let end = &line[content.len()..];
+                self.lines[index] = format!("{}:{}{}{}", name, spacing, value, end);
+                return Ok(());
+            }
+
+            // after the last field, comments at the end of the record stay last
+            let last = range
+                .clone()
+                .rev()
+                .find(|&index| !self.lines[index].trim().starts_with('#'))
+                .unwrap_or(range.start);
+            let line = &mut self.lines[last];
+            let indent = &line[..line.len() - line.trim_start().len()];
+            let mut added = format!("{}{}: {}", indent, standard.unwrap_or(key), value);
+            match &line[line.trim_end_matches(['\r', '\n']).len()..] {
+                "" => line.push('\n'),
+                ending => added.push_str(ending),
+            }
+            self.lines.insert(last + 1, added);
+            self.records[record].end += 1;
+            for range in &mut self.records[record + 1..] {
+                *range = range.start + 1..range.end + 1;
+            }
+            Ok(())
+        }
+
+        /// Value as the writer spells it
+        fn encode(&self, standard: Option<&str>, value: &str) -> Result<String, ParseError> {
+            let quoted = match standard {
+                Some("DESCRIPTION") => true,
+                Some(_) => false,
+                None => text_parser::needs_quotes(value),
+            };
+            match self.options.values.description {
+                DescriptionQuotes::Syntax if quoted => Ok(text_parser::quote(value)),
+                _ if value.contains(['\n', '\r']) => Err(ParseError::InvalidArgument(
+                    "only quoted values can contain line breaks",
+                )),
+                _ => Ok(value.to_string()),
+            }
+        }
+
+        /// Line of the field the reader takes the value from, the last one of a repeated key
+        fn find(&self, record: usize, key: &str) -> Option<usize> {
+            let standard = text_parser::standard_key(key, self.options.mode);
+            self.records.get(record)?.clone().rev().find(|&index| {
+                let line = self.lines[index].trim();
+                !line.starts_with('#')
+                    && line.split_once(':').is_some_and(|(name, _)| {
+                        let name = name.trim();
+                        match standard {
+                            Some(standard) => {
+                                text_parser::standard_key(name, self.options.mode) == Some(standard)
+                            }
+                            None => name == key,
+                        }
+                    })
+            })
+        }
+    }
+
+    impl Display for TextDocument {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            self.lines.iter().try_for_each(|line| f.write_str(line))
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use core::{Record, TransactionStatus, TransactionType};
+    use core::{
+        DescriptionQuotes, ErrorStrategy, ParseError, ParseOptions, Position, Reader, Record,
+        RecordWriter, TimestampFormat, TransactionStatus, TransactionType, ValueFormat, Writer,
+    };
     use std::io::{BufRead, BufReader, Cursor};
 
+    use super::text_document::TextDocument;
     use super::*;
 
+    /// Record the single record tests build their variants from
+    fn record() -> Record {
+        Record {
+            tx_id: 1,
+            tx_type: TransactionType::Deposit,
+            from_user_id: 0,
+            to_user_id: 2,
+            amount: 100.into(),
+            timestamp: 1633036860000.into(),
+            status: TransactionStatus::Success,
+            description: "Record number 1".to_string(),
+            extensions: Default::default(),
+        }
+    }
+
     #[test]
     fn test_load_data() {
         let data = "
@@ -187,7 +883,7 @@ mod tests {
             TIMESTAMP: 1633036980000
             ";
 
-        let cursor = Cursor::new(&data[..]);
+        let cursor = Cursor::new(data);
         let r = text_parser::read_from(cursor);
 
         assert_eq!(r.unwrap().len(), 3);
@@ -201,30 +897,33 @@ mod tests {
                 tx_type: TransactionType::Deposit,
                 from_user_id: 0,
                 to_user_id: 9223372036854775807,
-                amount: 100,
-                timestamp: 1633036860000,
+                amount: 100.into(),
+                timestamp: 1633036860000.into(),
                 status: TransactionStatus::Failure,
                 description: "Record number 1".to_string(),
+                extensions: Default::default(),
             },
             Record {
                 tx_id: 1000000000000001,
                 tx_type: TransactionType::Transfer,
                 from_user_id: 9223372036854775807,
                 to_user_id: 9223372036854775807,
-                amount: 200,
-                timestamp: 1633036920000,
+                amount: 200.into(),
+                timestamp: 1633036920000.into(),
                 status: TransactionStatus::Pending,
                 description: "Record number 2".to_string(),
+                extensions: Default::default(),
             },
             Record {
                 tx_id: 1000000000000002,
                 tx_type: TransactionType::Withdrawal,
                 from_user_id: 599094029349995112,
                 to_user_id: 0,
-                amount: 300,
-                timestamp: 1633036980000,
+                amount: 300.into(),
+                timestamp: 1633036980000.into(),
                 status: TransactionStatus::Success,
                 description: "Record number 3".to_string(),
+                extensions: Default::default(),
             },
         ];
 
@@ -239,6 +938,331 @@ mod tests {
             .collect::<Result<_, _>>()
             .unwrap();
 
-        assert_eq!(lines.len(), 26);
+        assert_eq!(lines.len(), 29);
+        assert_eq!(lines[0], "# Record 1 (DEPOSIT)");
+        assert_eq!(lines[20], "# Record 3 (WITHDRAWAL)");
+    }
+
+    #[test]
+    fn test_field_order() {
+        let reference = std::fs::read_to_string("../records_example.txt").unwrap();
+        let first = reference.split("\n\n").next().unwrap();
+        let options = text_parser::TextOptions {
+            values: ValueFormat {
+                description: DescriptionQuotes::Legacy,
+                ..ValueFormat::default()
+            },
+            field_order: text_parser::parse_field_order(
+                "TX_TYPE,TO_USER_ID,FROM_USER_ID,TIMESTAMP,DESCRIPTION,TX_ID,AMOUNT,STATUS",
+            )
+            .unwrap(),
+            ..text_parser::TextOptions::default()
+        };
+        let records: Vec<Record> = text_parser::records_with(first.as_bytes(), &options)
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let mut output = Vec::new();
+        let mut writer = text_parser::TextWriter::with_options(&mut output, &options);
+        writer.write_record(&records[0]).unwrap();
+        writer.finish().unwrap();
+        drop(writer);
+        assert_eq!(String::from_utf8(output).unwrap(), format!("{}\n", first));
+
+        let options = text_parser::TextOptions {
+            field_order: ["TX_ID"; 8],
+            ..text_parser::TextOptions::default()
+        };
+        let mut writer = text_parser::TextWriter::with_options(Vec::new(), &options);
+        assert!(matches!(
+            writer.write_record(&records[0]),
+            Err(ParseError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_traits_round_trip() {
+        let record = Record {
+            tx_id: 1000000000000000,
+            tx_type: TransactionType::Deposit,
+            from_user_id: 0,
+            to_user_id: 9223372036854775807,
+            amount: 100.into(),
+            timestamp: 1633036860000.into(),
+            status: TransactionStatus::Failure,
+            description: "Record number 1".to_string(),
+            extensions: Default::default(),
+        };
+
+        let mut buffer = Vec::new();
+        text_parser::Text::write_to(&mut buffer, vec![record.clone()]).unwrap();
+
+        let r = text_parser::Text::read_from(&buffer[..]).unwrap();
+
+        assert_eq!(r, vec![record]);
+    }
+
+    #[test]
+    fn test_error_position() {
+        let data = "# Record 1 (DEPOSIT)\nTX_ID: 1\nTX_TYPE: DEPOSIT\n";
+
+        let mut it = text_parser::records(data.as_bytes());
+
+        let error = it.next().unwrap().unwrap_err();
+        assert!(matches!(error.kind(), ParseError::MissingField(_)));
+        assert_eq!(
+            error.position(),
+            Some(Position::Line {
+                line: 2,
+                column: None
+            })
+        );
+
+        assert!(it.next().is_none());
+
+        let data = "# Record 2 (DEPOSIT)\nTX_ID: 2\n  AMOUNT:  -5\n";
+        let error = text_parser::read_from(data.as_bytes()).unwrap_err();
+        assert_eq!(error.to_string(), "3:12: Invalid number in AMOUNT");
+    }
+
+    #[test]
+    fn test_text_modes() {
+        let data = "TX_ID: 1\nTX_TYPE: DEPOSIT\nFROM_USER_ID: 0\nTO_USER_ID: 2\nAMOUNT: 100\nTIMESTAMP: 1633036860000\nSTATUS: SUCCESS\nDESCRIPTION: x\n";
+
+        let repeated = format!("{}  AMOUNT: 200\n", data);
+        let error = text_parser::read_from(repeated.as_bytes()).unwrap_err();
+        assert_eq!(error.to_string(), "9:3: Duplicate key 'AMOUNT'");
+        let error = text_parser::read_from(data.replace("TX_ID: 1", "TX_ID: one").as_bytes());
+        assert_eq!(
+            error.unwrap_err().to_string(),
+            "1:8: Invalid number in TX_ID"
+        );
+        let lower = data.replace("STATUS", "status");
+        assert!(text_parser::read_from(lower.as_bytes()).is_err());
+
+        let options = text_parser::TextOptions {
+            mode: text_parser::TextMode::Permissive,
+            ..text_parser::TextOptions::default()
+        };
+        let read = |data: &str| -> Result<Vec<Record>, ParseError> {
+            text_parser::records_with(data.as_bytes(), &options).collect()
+        };
+        let messy = format!(
+            "{}Merchant_Ref: M-42\n: no key\n",
+            lower.replace("TX_TYPE", "Tx_Type")
+        );
+        let record = read(&messy).unwrap().remove(0);
+        assert_eq!(record.status, TransactionStatus::Success);
+        assert_eq!(record.extensions["Merchant_Ref"], "M-42");
+        assert_eq!(read(&repeated).unwrap()[0].amount, 200.into());
+        assert!(read(&data.replace("TX_ID: 1", "TX_ID: one")).is_err());
+    }
+
+    #[test]
+    fn test_collect_errors() {
+        let data = "# Record 1 (DEPOSIT)\nTX_ID: 1\nTX_TYPE: PAYMENT\nAMOUNT: 100\n\n# Record 2 (DEPOSIT)\nTX_ID: 2\nTX_TYPE: DEPOSIT\nFROM_USER_ID: 0\nTO_USER_ID: 1\nAMOUNT: 100\nTIMESTAMP: 1633036860000\nSTATUS: SUCCESS\nDESCRIPTION: ok\n";
+
+        let options = ParseOptions::new(ErrorStrategy::Collect);
+        let r = text_parser::read_with(data.as_bytes(), &options).unwrap();
+
+        assert_eq!(r.records.len(), 1);
+        assert_eq!(r.records[0].tx_id, 2);
+        assert_eq!(r.diagnostics.len(), 1);
+        assert_eq!(
+            r.diagnostics[0].raw,
+            b"# Record 1 (DEPOSIT)\nTX_ID: 1\nTX_TYPE: PAYMENT\nAMOUNT: 100\n"
+        );
+
+        let options = ParseOptions::new(ErrorStrategy::FailFast);
+        assert!(text_parser::read_with(data.as_bytes(), &options).is_err());
+    }
+
+    #[test]
+    fn test_rfc3339_timestamps() {
+        let record = record();
+        let mut options = text_parser::TextOptions::default();
+        options.values.timestamp = TimestampFormat::Rfc3339 {
+            offset_minutes: 180,
+        };
+
+        let mut output = Vec::new();
+        let mut writer = text_parser::TextWriter::with_options(&mut output, &options);
+        writer.write_record(&record).unwrap();
+        writer.finish().unwrap();
+        drop(writer);
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("TIMESTAMP: 2021-10-01T00:21:00+03:00\n"));
+        assert_eq!(text_parser::read_from(output.as_bytes()).unwrap(), [record]);
+    }
+
+    #[test]
+    fn test_extensions() {
+        let data = "TX_ID: 1\nTX_TYPE: DEPOSIT\nFROM_USER_ID: 0\nTO_USER_ID: 2\nAMOUNT: 100\nTIMESTAMP: 1633036860000\nSTATUS: SUCCESS\nDESCRIPTION: x\nMERCHANT_REF:  M-42 \n";
+
+        let record = text_parser::read_from(data.as_bytes()).unwrap().remove(0);
+        assert_eq!(record.extensions["MERCHANT_REF"], "M-42");
+
+        let mut output = Vec::new();
+        text_parser::write_to(&mut output, [record.clone()]).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.ends_with("DESCRIPTION: \"x\"\nMERCHANT_REF: M-42\n"));
+        assert_eq!(
+            text_parser::read_from(output.as_bytes()).unwrap(),
+            std::slice::from_ref(&record)
+        );
+
+        let mut dotted = record.clone();
+        dotted
+            .extensions
+            .insert("ref-2.v_1".to_string(), "# not a comment".to_string());
+        let mut output = Vec::new();
+        text_parser::write_to(&mut output, [dotted.clone()]).unwrap();
+        assert_eq!(text_parser::read_from(&output[..]).unwrap(), [dotted]);
+
+        let mut invalid = record.clone();
+        invalid
+            .extensions
+            .insert("Amount".to_string(), "5".to_string());
+        let error = text_parser::write_to(&mut Vec::new(), [invalid]).unwrap_err();
+        assert_eq!(error.to_string(), "Invalid extension field 'Amount'");
+
+        for name in ["#REF", "A B", "A,B", "A\"B", "A=B"] {
+            let mut invalid = record.clone();
+            invalid.extensions.insert(name.to_string(), "1".to_string());
+            let error = text_parser::write_to(&mut Vec::new(), [invalid]).unwrap_err();
+            assert!(matches!(error, ParseError::InvalidExtension(_)), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_description_quotes() {
+        let data = "TX_ID: 1\nTX_TYPE: DEPOSIT\nFROM_USER_ID: 0\nTO_USER_ID: 2\nAMOUNT: 100\nTIMESTAMP: 1633036860000\nSTATUS: SUCCESS\nDESCRIPTION: \"Record \\\"number\\\" 1\\\\\"\n";
+        let mut record = text_parser::read_from(data.as_bytes()).unwrap().remove(0);
+
+        assert_eq!(record.description, "Record \"number\" 1\\");
+
+        for description in ["", "  padded  ", "\"", "\\\"", "plain"] {
+            record.description = description.to_string();
+            let mut output = Vec::new();
+            text_parser::write_to(&mut output, [record.clone()]).unwrap();
+            let r = text_parser::read_from(&output[..]).unwrap();
+            assert_eq!(r[0].description, description);
+        }
+
+        let mut options = text_parser::TextOptions::default();
+        options.values.description = core::DescriptionQuotes::Legacy;
+        let r = text_parser::records_with(data.as_bytes(), &options).next();
+        assert_eq!(
+            r.unwrap().unwrap().description,
+            "\"Record \\\"number\\\" 1\\\\\""
+        );
+
+        for (description, column) in [
+            ("\"open", 14),
+            ("\"a\" b", 14),
+            ("\"\\q\"", 14),
+            ("\"\\u{d800}\"", 14),
+        ] {
+            let data = data.replace("\"Record \\\"number\\\" 1\\\\\"", description);
+            let error = text_parser::read_from(data.as_bytes()).unwrap_err();
+            assert_eq!(error.to_string(), format!("8:{}: Malformed line", column));
+        }
+    }
+
+    #[test]
+    fn test_multiline_values() {
+        let mut record = text_parser::read_from(&b"TX_ID: 1\nTX_TYPE: DEPOSIT\nFROM_USER_ID: 0\nTO_USER_ID: 2\nAMOUNT: 100\nTIMESTAMP: 1633036860000\nSTATUS: SUCCESS\nDESCRIPTION: \"two\\nlines\\u{7}\"\nNOTE: \" \\tpadded\\r\\n\"\n"[..])
+            .unwrap()
+            .remove(0);
+        assert_eq!(record.description, "two\nlines\u{7}");
+        assert_eq!(record.extensions["NOTE"], " \tpadded\r\n");
+
+        record.description = "line\r\n\n\"end\"\\\0é".to_string();
+        record
+            .extensions
+            .insert("PLAIN".to_string(), "as is".to_string());
+        record
+            .extensions
+            .insert("QUOTE".to_string(), "\"as is\"".to_string());
+        let mut output = Vec::new();
+        text_parser::write_to(&mut output, [record.clone(), record.clone()]).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("DESCRIPTION: \"line\\r\\n\\n\\\"end\\\"\\\\\\u{0}é\"\n"));
+        assert!(output.contains("PLAIN: as is\nQUOTE: \"\\\"as is\\\"\"\n"));
+        assert_eq!(output.lines().count(), 2 * 12 + 1);
+        assert_eq!(
+            text_parser::read_from(output.as_bytes()).unwrap(),
+            [record.clone(), record.clone()]
+        );
+
+        let mut options = text_parser::TextOptions::default();
+        options.values.description = DescriptionQuotes::Legacy;
+        let mut writer = text_parser::TextWriter::with_options(Vec::new(), &options);
+        assert!(matches!(
+            writer.write_record(&record),
+            Err(ParseError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_document_is_lossless() {
+        let reference = std::fs::read_to_string("../records_example.txt").unwrap();
+        let options = text_parser::TextOptions::default();
+        let mut document = TextDocument::parse(&reference, &options);
+
+        assert_eq!(document.to_string(), reference);
+        assert_eq!(document.len(), 1000);
+
+        document.set(1, "STATUS", "FAILURE").unwrap();
+        let edited = document.to_string();
+        let changed: Vec<(&str, &str)> = reference
+            .lines()
+            .zip(edited.lines())
+            .filter(|(before, after)| before != after)
+            .collect();
+        assert_eq!(changed, [("STATUS: PENDING", "STATUS: FAILURE")]);
+
+        let mut output = Vec::new();
+        document.write_to(&mut output).unwrap();
+        let records = text_parser::read_from(&output[..]).unwrap();
+        assert_eq!(records[1].status, TransactionStatus::Failure);
+    }
+
+    #[test]
+    fn test_document_edits() {
+        let data = "# ops: checked\r\n  TX_ID :\t1  \r\n  TX_TYPE: DEPOSIT\r\n# trailing note\r\n\r\n\r\nTX_ID: 2";
+        let options = text_parser::TextOptions::default();
+        let mut document = TextDocument::parse(data, &options);
+        assert_eq!(document.len(), 2);
+
+        document.set(0, "TX_ID", "10").unwrap();
+        document.set(0, "DESCRIPTION", "two\nlines").unwrap();
+        document.set(0, "NOTE", " padded").unwrap();
+        document.set(1, "AMOUNT", "5").unwrap();
+        assert_eq!(
+            document.to_string(),
+            "# ops: checked\r\n  TX_ID :\t10  \r\n  TX_TYPE: DEPOSIT\r\n  DESCRIPTION: \"two\\nlines\"\r\n  NOTE: \" padded\"\r\n# trailing note\r\n\r\n\r\nTX_ID: 2\nAMOUNT: 5"
+        );
+        assert_eq!(document.get(0, "DESCRIPTION"), Some("\"two\\nlines\""));
+        assert_eq!(document.get(1, "STATUS"), None);
+
+        let before = document.to_string();
+        assert!(matches!(
+            document.set(0, "AMOUNT", "ten"),
+            Err(ParseError::InvalidNumber("AMOUNT"))
+        ));
+        assert!(matches!(
+            document.set(0, "status", "SUCCESS"),
+            Err(ParseError::InvalidExtension(_))
+        ));
+        assert!(document.set(2, "TX_ID", "1").is_err());
+        assert_eq!(document.to_string(), before);
+
+        let records = document.records();
+        assert!(matches!(
+            records[1].as_ref().unwrap_err().kind(),
+            ParseError::MissingField(_)
+        ));
     }
 }